@@ -1,4 +1,4 @@
-use messagebus::{Action, Bus, Event, SharedMessage, TypeTag};
+use messagebus::{Action, Bus, Event, Headers, SharedMessage, TypeTag};
 use serde_derive::{Deserialize, Serialize};
 use std::borrow::Cow;
 
@@ -60,6 +60,8 @@ pub struct ProtocolHeader<'a> {
     pub flags: ProtocolHeaderFlags,
     pub body_type: BodyType,
     pub argument: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub headers: Option<Headers>,
 }
 
 impl<'a> ProtocolHeader<'a> {
@@ -70,6 +72,7 @@ impl<'a> ProtocolHeader<'a> {
             flags: ProtocolHeaderFlags::TT_BODY_AND_ARGUMENT,
             body_type,
             argument: mid,
+            headers: None,
         }
     }
 
@@ -80,6 +83,7 @@ impl<'a> ProtocolHeader<'a> {
             flags: ProtocolHeaderFlags::empty(),
             body_type: BodyType::None,
             argument: 0,
+            headers: None,
         }
     }
 
@@ -90,6 +94,7 @@ impl<'a> ProtocolHeader<'a> {
             flags: ProtocolHeaderFlags::empty(),
             body_type: BodyType::None,
             argument: 0,
+            headers: None,
         }
     }
 
@@ -100,6 +105,7 @@ impl<'a> ProtocolHeader<'a> {
             flags: ProtocolHeaderFlags::empty(),
             body_type: BodyType::None,
             argument: 0,
+            headers: None,
         }
     }
 
@@ -129,7 +135,7 @@ pub enum ProtocolItem {
     Nop,
     Event(Event<Box<dyn SharedMessage>, messagebus::error::GenericError>),
     Action(Action),
-    Send(u64, Box<dyn SharedMessage>, bool),
+    Send(u64, Box<dyn SharedMessage>, bool, Headers),
 }
 
 impl From<Action> for ProtocolItem {
@@ -144,16 +150,16 @@ impl From<Event<Box<dyn SharedMessage>, messagebus::error::GenericError>> for Pr
     }
 }
 
-impl From<(u64, Box<dyn SharedMessage>, bool)> for ProtocolItem {
-    fn from(msg: (u64, Box<dyn SharedMessage>, bool)) -> Self {
-        ProtocolItem::Send(msg.0, msg.1, msg.2)
+impl From<(u64, Box<dyn SharedMessage>, bool, Headers)> for ProtocolItem {
+    fn from(msg: (u64, Box<dyn SharedMessage>, bool, Headers)) -> Self {
+        ProtocolItem::Send(msg.0, msg.1, msg.2, msg.3)
     }
 }
 
 impl ProtocolItem {
-    pub fn unwrap_send(self) -> Result<(u64, Box<dyn SharedMessage>, bool), ProtocolItem> {
+    pub fn unwrap_send(self) -> Result<(u64, Box<dyn SharedMessage>, bool, Headers), ProtocolItem> {
         match self {
-            ProtocolItem::Send(a, b, c) => Ok((a, b, c)),
+            ProtocolItem::Send(a, b, c, d) => Ok((a, b, c, d)),
             other => Err(other),
         }
     }
@@ -166,6 +172,7 @@ impl ProtocolItem {
         let mut argument = 0;
         let mut type_tag = None;
         let mut body = None;
+        let mut headers = None;
         let mut flags = ProtocolHeaderFlags::empty();
 
         let kind = match self {
@@ -177,11 +184,15 @@ impl ProtocolItem {
                 Action::Sync => ProtocolHeaderActionKind::Synchronize,
                 _ => unimplemented!(),
             },
-            ProtocolItem::Send(mid, msg, req) => {
+            ProtocolItem::Send(mid, msg, req, hdrs) => {
                 let msg = msg
                     .as_shared_ref()
                     .ok_or(crate::error::Error::UnknownCodec)?;
 
+                if !hdrs.is_empty() {
+                    headers = Some(hdrs.clone());
+                }
+
                 argument = *mid;
                 flags.set(ProtocolHeaderFlags::ARGUMENT, *req);
                 flags.set(ProtocolHeaderFlags::BODY, true);
@@ -278,6 +289,7 @@ impl ProtocolItem {
                 flags,
                 body_type,
                 argument,
+                headers,
             },
             body,
         })
@@ -292,6 +304,7 @@ pub struct ProtocolPacket<'a> {
 
 impl<'a> ProtocolPacket<'a> {
     pub fn deserialize(self, _bus: &Bus) -> Result<ProtocolItem, crate::error::Error> {
+        let headers = self.header.headers;
         let type_tag: Option<TypeTag> = if self.header.flags.contains(ProtocolHeaderFlags::TYPE_TAG)
         {
             self.header
@@ -375,7 +388,12 @@ impl<'a> ProtocolPacket<'a> {
                             ))
                         })?;
 
-                        return Ok(ProtocolItem::Send(mid, body, req));
+                        return Ok(ProtocolItem::Send(
+                            mid,
+                            body,
+                            req,
+                            headers.unwrap_or_default(),
+                        ));
                     }
                     ProtocolHeaderActionKind::Nop => return Ok(ProtocolItem::Nop),
 
@@ -441,7 +459,7 @@ mod tests {
         BodyType, ProtocolHeader, ProtocolHeaderActionKind, ProtocolHeaderFlags, ProtocolPacket,
     };
     use messagebus::Event;
-    use messagebus::{derive::Message, Bus, TypeTagged};
+    use messagebus::{derive::Message, Bus, Headers, SharedMessage, TypeTagged};
     use serde_derive::{Deserialize, Serialize};
     use std::borrow::Cow;
 
@@ -464,6 +482,7 @@ mod tests {
                 flags: ProtocolHeaderFlags::TT_BODY_AND_ARGUMENT,
                 body_type: BodyType::Json,
                 argument: 222,
+                headers: None,
             },
             body: Some(Cow::Borrowed(br#"{"test":"my test","value":12}"#)),
         };
@@ -502,6 +521,7 @@ mod tests {
                 flags: ProtocolHeaderFlags::TT_ERROR_AND_ARGUMENT,
                 body_type: BodyType::Utf8,
                 argument: 222,
+                headers: None,
             },
             body: Some(Cow::Borrowed(br#"error description"#)),
         };
@@ -527,4 +547,35 @@ mod tests {
             _ => unreachable!(),
         }
     }
+
+    #[test]
+    fn test_proto_send_headers() {
+        let (bus, _) = Bus::build().build();
+
+        let msg = TestSharedMessage {
+            test: "with headers".into(),
+            value: 7,
+        };
+
+        let headers = Headers::new().with("correlation-id", "42");
+        let item = ProtocolItem::Send(
+            11,
+            Box::new(msg) as Box<dyn SharedMessage>,
+            true,
+            headers.clone(),
+        );
+
+        let mut body_buff = Vec::new();
+        let pkt = item.serialize(BodyType::Json, &mut body_buff).unwrap();
+        assert_eq!(pkt.header.headers.as_ref(), Some(&headers));
+
+        let (mid, msg, req, received) = pkt.deserialize(&bus).unwrap().unwrap_send().unwrap();
+
+        assert_eq!(mid, 11);
+        assert!(req);
+        assert_eq!(received.get("correlation-id"), Some("42"));
+
+        let m: Box<TestSharedMessage> = msg.as_any_boxed().downcast().unwrap();
+        assert_eq!(m.value, 7);
+    }
 }
@@ -92,7 +92,7 @@ impl SendUntypedReceiver for RedisRelay {
 
                         let channel = match &item {
                             ProtocolItem::Action(_) => "mbus_action".into(),
-                            ProtocolItem::Send(_, msg, _) => {
+                            ProtocolItem::Send(_, msg, _, _) => {
                                 format!("mbus_request::{}", msg.type_tag())
                             }
                             ProtocolItem::Event(_ev) => "mbus_response::".into(),
@@ -120,11 +120,13 @@ impl SendUntypedReceiver for RedisRelay {
         mid: u64,
         msg: Box<dyn Message>,
         req: bool,
-        _bus: &Bus,
+        bus: &Bus,
     ) -> Result<(), messagebus::error::Error<Box<dyn Message>>> {
         match msg.as_shared_boxed() {
             Ok(msg) => {
-                if let Err(err) = self.item_sender.send(Some((mid, msg, req).into())) {
+                let headers = bus.headers().cloned().unwrap_or_default();
+
+                if let Err(err) = self.item_sender.send(Some((mid, msg, req, headers).into())) {
                     Err(messagebus::error::Error::TryAgain(
                         err.0.unwrap().unwrap_send().unwrap().1.upcast_box(),
                     ))
@@ -232,11 +232,13 @@ impl SendUntypedReceiver for TcpRelay {
         mid: u64,
         msg: Box<dyn Message>,
         req: bool,
-        _bus: &Bus,
+        bus: &Bus,
     ) -> Result<(), messagebus::error::Error<Box<dyn Message>>> {
         match msg.as_shared_boxed() {
             Ok(msg) => {
-                if let Err(err) = self.item_sender.send(Some((mid, msg, req).into())) {
+                let headers = bus.headers().cloned().unwrap_or_default();
+
+                if let Err(err) = self.item_sender.send(Some((mid, msg, req, headers).into())) {
                     Err(messagebus::error::Error::TryAgain(
                         err.0.unwrap().unwrap_send().unwrap().1.upcast_box(),
                     ))
@@ -365,10 +367,10 @@ impl ReciveUntypedReceiver for TcpRelay {
                                             }
                                             continue;
                                         }
-                                        ProtocolItem::Send(mid, msg, req) => {
+                                        ProtocolItem::Send(mid, msg, req, headers) => {
                                             let self_id = self_id.clone();
                                             let sender = sender.clone();
-                                            let bus = bus.clone();
+                                            let bus = bus.with_headers(headers);
 
                                             let _ = tokio::spawn(async move {
                                                 if req {
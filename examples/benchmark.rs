@@ -125,6 +125,7 @@ async fn main() {
     let cfg = BufferUnorderedConfig {
         buffer_size: 8,
         max_parallel: 8,
+        ..Default::default()
     };
 
     let (b, poller) = Bus::build()
@@ -1,7 +1,7 @@
-use core::{marker::PhantomData, pin::Pin};
+use core::{marker::PhantomData, pin::Pin, time::Duration};
 
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     sync::{
         atomic::{AtomicU64, Ordering},
         Arc,
@@ -9,17 +9,21 @@ use std::{
 };
 
 use futures::{Future, FutureExt};
-use tokio::sync::Mutex;
+use tokio::{sync::Mutex, task::JoinHandle};
 
 use crate::{
+    dedup::{Dedup, DedupFilter, DedupWindow, Deduplicate},
+    envelop::TypeTag,
     error::StdSyncSendError,
     receiver::{
         BusPollerCallback, Receiver, ReciveTypedReceiver, SendTypedReceiver, SendUntypedReceiver,
         UntypedPollerCallback,
     },
-    receivers, AsyncBatchHandler, AsyncBatchSynchronizedHandler, AsyncHandler,
+    receivers::{self, Partitioned},
+    timer::{Period, Timer},
+    AsyncBatchHandler, AsyncBatchSynchronizedHandler, AsyncHandler, AsyncProducer,
     AsyncSynchronizedHandler, BatchHandler, BatchSynchronizedHandler, Bus, BusInner, Handler,
-    Message, Relay, SynchronizedHandler, Untyped,
+    Message, Middleware, Relay, SynchronizedHandler, Untyped,
 };
 
 static RECEVIER_ID_SEQ: AtomicU64 = AtomicU64::new(1);
@@ -34,13 +38,72 @@ where
 {
     type Config: Default;
 
+    fn circuit_breaker(_cfg: &Self::Config) -> receivers::CircuitBreakerConfig {
+        Default::default()
+    }
+
+    fn rate_limit(_cfg: &Self::Config) -> receivers::RateLimit {
+        Default::default()
+    }
+
+    fn overflow(_cfg: &Self::Config) -> receivers::OverflowPolicy {
+        Default::default()
+    }
+
+    fn resend_unused_resp(_cfg: &Self::Config) -> bool {
+        false
+    }
+
     fn build(cfg: Self::Config) -> (Self, UntypedPollerCallback)
     where
         Self: Sized;
 }
 
+pub(crate) fn new_receiver<T, M, R, E, S>(
+    queue: u64,
+    cfg: S::Config,
+) -> (Receiver, UntypedPollerCallback)
+where
+    T: 'static,
+    M: Message,
+    R: Message,
+    E: StdSyncSendError,
+    S: ReceiverSubscriberBuilder<T, M, R, E> + 'static,
+{
+    let breaker = S::circuit_breaker(&cfg);
+    let rate_limit = S::rate_limit(&cfg);
+    let overflow = S::overflow(&cfg);
+    let resend = S::resend_unused_resp(&cfg);
+    let (inner, poller) = S::build(cfg);
+
+    let receiver = Receiver::new::<M, R, E, S>(
+        RECEVIER_ID_SEQ.fetch_add(1, Ordering::Relaxed),
+        queue,
+        resend,
+        breaker,
+        rate_limit,
+        overflow,
+        inner,
+    );
+
+    (receiver, poller)
+}
+
+// handler instances made by `factory`, each behind its own lock, as the
+// item of a sharded registration
+fn sharded_item<T: Send + 'static, F: FnMut() -> T>(mut factory: F, shards: usize) -> Untyped {
+    assert!(shards > 0, "sharded registration needs at least one shard");
+
+    let items: Vec<Untyped> = (0..shards)
+        .map(|_| Arc::new(Mutex::new(factory())) as Untyped)
+        .collect();
+
+    Arc::new(items) as Untyped
+}
+
 pub struct SyncEntry;
 pub struct UnsyncEntry;
+pub struct ShardedEntry;
 
 #[must_use]
 pub struct RegisterEntry<K, T, F, P, B> {
@@ -50,17 +113,49 @@ pub struct RegisterEntry<K, T, F, P, B> {
     poller: P,
     receivers: HashSet<Receiver>,
     pollers: Vec<BusPollerCallback>,
+    group: Option<&'static str>,
     _m: PhantomData<(K, T)>,
 }
 
+// What a `RegisterEntry` hands its receivers and pollers to once it is done:
+// a module, a bus builder or a running bus.
+trait RegisterTarget {
+    fn add_receiver(&mut self, receiver: Receiver);
+    fn add_poller(&mut self, poller: BusPollerCallback);
+}
+
+#[allow(clippy::type_complexity)]
+fn register_entry<K, T, B: RegisterTarget>(
+    payload: B,
+    item: Untyped,
+) -> RegisterEntry<K, T, fn(&mut B, Receiver), fn(&mut B, BusPollerCallback), B> {
+    RegisterEntry {
+        item,
+        payload,
+        builder: B::add_receiver,
+        poller: B::add_poller,
+        receivers: HashSet::new(),
+        pollers: Vec::new(),
+        group: None,
+        _m: Default::default(),
+    }
+}
+
 impl<K, T: 'static, F, P, B> RegisterEntry<K, T, F, P, B>
 where
     F: FnMut(&mut B, Receiver),
     P: FnMut(&mut B, BusPollerCallback),
 {
+    // the receivers subscribed with this entry become members of the consumer
+    // group `name`: a message sent to the group reaches only one of them
+    pub fn group(mut self, name: &'static str) -> Self {
+        self.group = Some(name);
+        self
+    }
+
     pub fn done(mut self) -> B {
         for r in self.receivers {
-            (self.builder)(&mut self.payload, r);
+            (self.builder)(&mut self.payload, r.in_group(self.group));
         }
 
         for p in self.pollers {
@@ -80,15 +175,7 @@ impl<T, F, P, B> RegisterEntry<UnsyncEntry, T, F, P, B> {
         E: StdSyncSendError,
         S: ReceiverSubscriberBuilder<T, M, R, E> + 'static,
     {
-        let (inner, poller) = S::build(cfg);
-
-        let receiver = Receiver::new::<M, R, E, S>(
-            RECEVIER_ID_SEQ.fetch_add(1, Ordering::Relaxed),
-            queue,
-            true,
-            inner,
-        );
-
+        let (receiver, poller) = new_receiver::<T, M, R, E, S>(queue, cfg);
         let poller2 = receiver.start_polling();
         self.receivers.insert(receiver);
         self.pollers.push(poller(self.item.clone()));
@@ -146,23 +233,93 @@ impl<T, F, P, B> RegisterEntry<UnsyncEntry, T, F, P, B> {
     }
 }
 
-impl<T, F, P, B> RegisterEntry<SyncEntry, T, F, P, B> {
+impl<T, F, P, B> RegisterEntry<ShardedEntry, T, F, P, B> {
+    // messages are routed to a shard by the hash of their partition key;
+    // flush, sync and close go to all the shards
     pub fn subscribe<M, S, R, E>(mut self, queue: u64, cfg: S::Config) -> Self
     where
-        T: Send + Sync + 'static,
-        M: Message,
+        T: Send + 'static,
+        M: Partitioned,
         R: Message,
         E: StdSyncSendError,
         S: ReceiverSubscriberBuilder<T, M, R, E> + 'static,
+        S::Config: Clone,
+        S::Stream: 'static,
     {
-        let (inner, poller) = S::build(cfg);
+        let shards = self.item.clone().downcast::<Vec<Untyped>>().unwrap().len();
 
-        let receiver = Receiver::new::<M, R, E, S>(
-            RECEVIER_ID_SEQ.fetch_add(1, Ordering::Relaxed),
+        let (receiver, poller) = new_receiver::<T, M, R, E, receivers::Sharded<S, M>>(
             queue,
-            true,
-            inner,
+            receivers::ShardedConfig { shards, inner: cfg },
         );
+
+        let poller2 = receiver.start_polling();
+        self.receivers.insert(receiver);
+        self.pollers.push(poller(self.item.clone()));
+        self.pollers.push(poller2);
+
+        self
+    }
+
+    #[inline]
+    pub fn subscribe_sync<M>(self, queue: u64, cfg: receivers::SynchronizedConfig) -> Self
+    where
+        T: SynchronizedHandler<M> + Send + 'static,
+        M: Partitioned,
+        T::Response: Message,
+    {
+        self.subscribe::<M, receivers::SynchronizedSync<M, T::Response, T::Error>, T::Response, T::Error>(queue, cfg)
+    }
+
+    #[inline]
+    pub fn subscribe_async<M>(self, queue: u64, cfg: receivers::SynchronizedConfig) -> Self
+    where
+        T: AsyncSynchronizedHandler<M> + Send + 'static,
+        M: Partitioned,
+        T::Response: Message,
+    {
+        self.subscribe::<M, receivers::SynchronizedAsync<M, T::Response, T::Error>, T::Response, T::Error>(queue, cfg)
+    }
+
+    #[inline]
+    pub fn subscribe_batch_sync<M>(
+        self,
+        queue: u64,
+        cfg: receivers::SynchronizedBatchedConfig,
+    ) -> Self
+    where
+        T: BatchSynchronizedHandler<M> + Send + 'static,
+        M: Partitioned,
+        T::Response: Message,
+    {
+        self.subscribe::<M, receivers::SynchronizedBatchedSync<M, T::Response, T::Error>, T::Response, T::Error>(queue, cfg)
+    }
+
+    #[inline]
+    pub fn subscribe_batch_async<M>(
+        self,
+        queue: u64,
+        cfg: receivers::SynchronizedBatchedConfig,
+    ) -> Self
+    where
+        T: AsyncBatchSynchronizedHandler<M> + Send + 'static,
+        M: Partitioned,
+        T::Response: Message,
+    {
+        self.subscribe::<M, receivers::SynchronizedBatchedAsync<M, T::Response, T::Error>, T::Response, T::Error>(queue, cfg)
+    }
+}
+
+impl<T, F, P, B> RegisterEntry<SyncEntry, T, F, P, B> {
+    pub fn subscribe<M, S, R, E>(mut self, queue: u64, cfg: S::Config) -> Self
+    where
+        T: Send + Sync + 'static,
+        M: Message,
+        R: Message,
+        E: StdSyncSendError,
+        S: ReceiverSubscriberBuilder<T, M, R, E> + 'static,
+    {
+        let (receiver, poller) = new_receiver::<T, M, R, E, S>(queue, cfg);
         let poller2 = receiver.start_polling();
         self.receivers.insert(receiver);
         self.pollers.push(poller(self.item.clone()));
@@ -219,12 +376,33 @@ impl<T, F, P, B> RegisterEntry<SyncEntry, T, F, P, B> {
     {
         self.subscribe::<M, receivers::BufferUnorderedBatchedAsync<M, T::Response, T::Error>, T::Response, T::Error>(queue, cfg)
     }
+
+    #[inline]
+    pub fn subscribe_producer<M>(self, queue: u64, cfg: receivers::AsyncProducerConfig) -> Self
+    where
+        T: AsyncProducer<M> + Send + Sync + 'static,
+        M: Message,
+        T::Item: Clone,
+        T::Response: Message,
+    {
+        self.subscribe::<M, receivers::AsyncProducer<M, T::Response, T::Error>, T::Response, T::Error>(queue, cfg)
+    }
 }
 
 #[derive(Default)]
 pub struct Module {
-    receivers: HashSet<Receiver>,
-    pollings: Vec<BusPollerCallback>,
+    pub(crate) receivers: HashSet<Receiver>,
+    pub(crate) pollings: Vec<BusPollerCallback>,
+}
+
+impl RegisterTarget for Module {
+    fn add_receiver(&mut self, receiver: Receiver) {
+        self.receivers.insert(receiver);
+    }
+
+    fn add_poller(&mut self, poller: BusPollerCallback) {
+        self.pollings.push(poller);
+    }
 }
 
 impl Module {
@@ -235,9 +413,28 @@ impl Module {
         }
     }
 
-    pub fn register_relay<S: Relay + Send + Sync + 'static>(mut self, inner: S) -> Self {
+    pub fn register_relay<S: Relay + Send + Sync + 'static>(self, inner: S) -> Self {
+        self.register_relay_ext(inner, None)
+    }
+
+    // relay joining the consumer group `group`, so the group can have members
+    // in other processes
+    pub fn register_group_relay<S: Relay + Send + Sync + 'static>(
+        self,
+        inner: S,
+        group: &'static str,
+    ) -> Self {
+        self.register_relay_ext(inner, Some(group))
+    }
+
+    fn register_relay_ext<S: Relay + Send + Sync + 'static>(
+        mut self,
+        inner: S,
+        group: Option<&'static str>,
+    ) -> Self {
         let receiver =
-            Receiver::new_relay::<S>(RECEVIER_ID_SEQ.fetch_add(1, Ordering::Relaxed), inner);
+            Receiver::new_relay::<S>(RECEVIER_ID_SEQ.fetch_add(1, Ordering::Relaxed), inner)
+                .in_group(group);
         self.pollings.push(receiver.start_polling());
         self.receivers.insert(receiver);
 
@@ -254,17 +451,7 @@ impl Module {
         impl FnMut(&mut Self, Box<dyn FnOnce(Bus) -> Pin<Box<dyn Future<Output = ()> + Send>>>),
         Self,
     > {
-        RegisterEntry {
-            item: Arc::new(item) as Untyped,
-            payload: self,
-            builder: |p: &mut Self, r| {
-                p.receivers.insert(r);
-            },
-            poller: |p: &mut Self, poller| p.pollings.push(poller),
-            receivers: HashSet::new(),
-            pollers: Vec::new(),
-            _m: Default::default(),
-        }
+        register_entry(self, Arc::new(item) as Untyped)
     }
 
     pub fn register_unsync<T: Send + 'static>(
@@ -277,19 +464,22 @@ impl Module {
         impl FnMut(&mut Self, Box<dyn FnOnce(Bus) -> Pin<Box<dyn Future<Output = ()> + Send>>>),
         Self,
     > {
-        let item = Arc::new(Mutex::new(item)) as Untyped;
-
-        RegisterEntry {
-            item,
-            payload: self,
-            builder: |p: &mut Self, r| {
-                p.receivers.insert(r);
-            },
-            poller: |p: &mut Self, poller| p.pollings.push(poller),
-            receivers: HashSet::new(),
-            pollers: Vec::new(),
-            _m: Default::default(),
-        }
+        register_entry(self, Arc::new(Mutex::new(item)) as Untyped)
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn register_sharded<T: Send + 'static, F: FnMut() -> T>(
+        self,
+        factory: F,
+        shards: usize,
+    ) -> RegisterEntry<
+        ShardedEntry,
+        T,
+        impl FnMut(&mut Self, Receiver),
+        impl FnMut(&mut Self, Box<dyn FnOnce(Bus) -> Pin<Box<dyn Future<Output = ()> + Send>>>),
+        Self,
+    > {
+        register_entry(self, sharded_item(factory, shards))
     }
 
     pub fn add_module(mut self, module: Module) -> Self {
@@ -301,20 +491,108 @@ impl Module {
 }
 
 pub struct BusBuilder {
-    inner: Module,
+    pub(crate) inner: Module,
+    seed: Option<u64>,
+    middlewares: Vec<Box<dyn Middleware>>,
+    timers: Vec<Arc<Timer>>,
+    dedup: HashMap<TypeTag, Box<dyn DedupFilter>>,
+    pub(crate) pipelines: HashMap<TypeTag, Vec<Vec<Receiver>>>,
+    ttl: HashMap<TypeTag, Duration>,
+}
+
+impl RegisterTarget for BusBuilder {
+    fn add_receiver(&mut self, receiver: Receiver) {
+        self.inner.add_receiver(receiver);
+    }
+
+    fn add_poller(&mut self, poller: BusPollerCallback) {
+        self.inner.add_poller(poller);
+    }
 }
 
 impl BusBuilder {
     pub(crate) fn new() -> Self {
         Self {
             inner: Module::new(),
+            seed: None,
+            middlewares: Vec::new(),
+            timers: Vec::new(),
+            dedup: HashMap::new(),
+            pipelines: HashMap::new(),
+            ttl: HashMap::new(),
         }
     }
 
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn middleware<T: Middleware>(mut self, middleware: T) -> Self {
+        self.middlewares.push(Box::new(middleware));
+        self
+    }
+
+    // sends a message made by `factory` every `period`, until the bus is closed
+    pub fn register_interval<M, F>(self, period: Duration, factory: F) -> Self
+    where
+        M: Message + Clone,
+        F: FnMut() -> M + Send + 'static,
+    {
+        assert!(!period.is_zero(), "interval period must be non-zero");
+
+        self.register_timer(Period::Interval(period), factory)
+    }
+
+    // same as `register_interval`, but fires on every (UTC) time matching `schedule`
+    pub fn register_cron<M, F>(self, schedule: cron::Schedule, factory: F) -> Self
+    where
+        M: Message + Clone,
+        F: FnMut() -> M + Send + 'static,
+    {
+        self.register_timer(Period::Cron(Box::new(schedule)), factory)
+    }
+
+    fn register_timer<M, F>(mut self, period: Period, factory: F) -> Self
+    where
+        M: Message + Clone,
+        F: FnMut() -> M + Send + 'static,
+    {
+        let timer = Arc::new(Timer::new::<M>(period));
+        self.inner.pollings.push(timer.clone().start(factory));
+        self.timers.push(timer);
+
+        self
+    }
+
+    pub fn deduplicate<M: Deduplicate>(mut self, window: DedupWindow) -> Self {
+        self.dedup
+            .insert(M::type_tag_(), Box::new(Dedup::<M>::new(window)));
+
+        self
+    }
+
+    // messages of type `M` waiting in a receiver queue for longer than `ttl`
+    // are discarded as expired, unless sent with a ttl of their own
+    pub fn message_ttl<M: Message>(mut self, ttl: Duration) -> Self {
+        self.ttl.insert(M::type_tag_(), ttl);
+        self
+    }
+
     pub fn register_relay<S: Relay + Send + Sync + 'static>(self, inner: S) -> Self {
         let inner = self.inner.register_relay(inner);
 
-        BusBuilder { inner }
+        BusBuilder { inner, ..self }
+    }
+
+    pub fn register_group_relay<S: Relay + Send + Sync + 'static>(
+        self,
+        inner: S,
+        group: &'static str,
+    ) -> Self {
+        let inner = self.inner.register_group_relay(inner, group);
+
+        BusBuilder { inner, ..self }
     }
 
     pub fn register<T: Send + Sync + 'static>(
@@ -327,17 +605,7 @@ impl BusBuilder {
         impl FnMut(&mut Self, Box<dyn FnOnce(Bus) -> Pin<Box<dyn Future<Output = ()> + Send>>>),
         Self,
     > {
-        RegisterEntry {
-            item: Arc::new(item) as Untyped,
-            payload: self,
-            builder: |p: &mut Self, r| {
-                p.inner.receivers.insert(r);
-            },
-            poller: |p: &mut Self, poller| p.inner.pollings.push(poller),
-            receivers: HashSet::new(),
-            pollers: Vec::new(),
-            _m: Default::default(),
-        }
+        register_entry(self, Arc::new(item) as Untyped)
     }
 
     pub fn register_unsync<T: Send + 'static>(
@@ -350,17 +618,22 @@ impl BusBuilder {
         impl FnMut(&mut Self, Box<dyn FnOnce(Bus) -> Pin<Box<dyn Future<Output = ()> + Send>>>),
         Self,
     > {
-        RegisterEntry {
-            item: Arc::new(Mutex::new(item)) as Untyped,
-            payload: self,
-            builder: |p: &mut Self, r| {
-                p.inner.receivers.insert(r);
-            },
-            poller: |p: &mut Self, poller| p.inner.pollings.push(poller),
-            receivers: HashSet::new(),
-            pollers: Vec::new(),
-            _m: Default::default(),
-        }
+        register_entry(self, Arc::new(Mutex::new(item)) as Untyped)
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn register_sharded<T: Send + 'static, F: FnMut() -> T>(
+        self,
+        factory: F,
+        shards: usize,
+    ) -> RegisterEntry<
+        ShardedEntry,
+        T,
+        impl FnMut(&mut Self, Receiver),
+        impl FnMut(&mut Self, Box<dyn FnOnce(Bus) -> Pin<Box<dyn Future<Output = ()> + Send>>>),
+        Self,
+    > {
+        register_entry(self, sharded_item(factory, shards))
     }
 
     pub fn add_module(mut self, module: Module) -> Self {
@@ -371,7 +644,16 @@ impl BusBuilder {
 
     pub fn build(self) -> (Bus, impl Future<Output = ()>) {
         let bus = Bus {
-            inner: Arc::new(BusInner::new(self.inner.receivers)),
+            inner: Arc::new(BusInner::new(
+                self.inner.receivers,
+                self.seed,
+                self.middlewares,
+                self.timers,
+                self.dedup,
+                self.pipelines,
+                self.ttl,
+            )),
+            context: None,
         };
 
         let mut futs = Vec::with_capacity(self.inner.pollings.len() * 2);
@@ -386,3 +668,130 @@ impl BusBuilder {
         (bus, poller)
     }
 }
+
+// The receivers registered on a running bus stay there until `remove` is
+// called; dropping the handle leaves them registered for good.
+#[must_use]
+pub struct ModuleHandle {
+    bus: Bus,
+    receivers: Vec<Receiver>,
+    pollings: Vec<JoinHandle<()>>,
+}
+
+impl ModuleHandle {
+    fn new(bus: Bus) -> Self {
+        Self {
+            bus,
+            receivers: Vec::new(),
+            pollings: Vec::new(),
+        }
+    }
+
+    fn attach(&mut self, receivers: Vec<Receiver>) {
+        // init goes out before the receivers become routable, so pollers know
+        // their receiver id ahead of the first message
+        for r in receivers.iter() {
+            if let Err(err) = r.init(&self.bus) {
+                error!("Init failed on {}: {}", r.name(), err);
+            }
+        }
+
+        self.bus.inner.add_receivers(&receivers);
+        self.receivers.extend(receivers);
+    }
+
+    fn spawn(&mut self, poller: BusPollerCallback) {
+        self.pollings
+            .push(tokio::task::spawn(poller(self.bus.clone())));
+    }
+
+    pub fn receiver_ids(&self) -> impl Iterator<Item = u64> + '_ {
+        self.receivers.iter().map(Receiver::id)
+    }
+
+    pub async fn remove(self) {
+        let ids: Vec<_> = self.receiver_ids().collect();
+        self.bus.inner.remove_receivers(&ids);
+
+        for r in self.receivers.iter() {
+            r.close(&self.bus).await;
+        }
+
+        futures::future::join_all(self.pollings).await;
+    }
+}
+
+impl RegisterTarget for ModuleHandle {
+    fn add_receiver(&mut self, receiver: Receiver) {
+        self.attach(vec![receiver]);
+    }
+
+    fn add_poller(&mut self, poller: BusPollerCallback) {
+        self.spawn(poller);
+    }
+}
+
+impl Bus {
+    pub fn add_module(&self, module: Module) -> ModuleHandle {
+        let mut handle = ModuleHandle::new(self.with_context(None));
+
+        handle.attach(module.receivers.into_iter().collect());
+        for poller in module.pollings {
+            handle.spawn(poller);
+        }
+
+        handle
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn register<T: Send + Sync + 'static>(
+        &self,
+        item: T,
+    ) -> RegisterEntry<
+        SyncEntry,
+        T,
+        impl FnMut(&mut ModuleHandle, Receiver),
+        impl FnMut(&mut ModuleHandle, BusPollerCallback),
+        ModuleHandle,
+    > {
+        register_entry(
+            ModuleHandle::new(self.with_context(None)),
+            Arc::new(item) as Untyped,
+        )
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn register_unsync<T: Send + 'static>(
+        &self,
+        item: T,
+    ) -> RegisterEntry<
+        UnsyncEntry,
+        T,
+        impl FnMut(&mut ModuleHandle, Receiver),
+        impl FnMut(&mut ModuleHandle, BusPollerCallback),
+        ModuleHandle,
+    > {
+        register_entry(
+            ModuleHandle::new(self.with_context(None)),
+            Arc::new(Mutex::new(item)) as Untyped,
+        )
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn register_sharded<T: Send + 'static, F: FnMut() -> T>(
+        &self,
+        factory: F,
+        shards: usize,
+    ) -> RegisterEntry<
+        ShardedEntry,
+        T,
+        impl FnMut(&mut ModuleHandle, Receiver),
+        impl FnMut(&mut ModuleHandle, BusPollerCallback),
+        ModuleHandle,
+    > {
+        register_entry(
+            ModuleHandle::new(self.with_context(None)),
+            sharded_item(factory, shards),
+        )
+    }
+}
@@ -0,0 +1,210 @@
+use core::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+use std::sync::Arc;
+
+use tokio::time::Instant;
+
+use crate::{scope::ScopeState, Headers};
+
+// Per-message state travelling with a message through receiver queues.
+// Messages sent from a handler carry the handler's context, so anything set
+// on a parent (e.g. cancellation, headers) is visible to all of its descendants.
+#[derive(Debug, Default)]
+pub(crate) struct Context {
+    parent: Option<Arc<Context>>,
+    headers: Option<Arc<Headers>>,
+    cancelled: AtomicBool,
+    scope: Option<Arc<ScopeState>>,
+    // a tracked context belongs to a single message sent within a scope and
+    // counts as the scope's outstanding work for as long as it is alive
+    tracked: bool,
+    // set on contexts owned by a single message once its receiver picks it
+    // up (or once it is evicted from the queue)
+    single: bool,
+    picked: AtomicBool,
+    // time-to-live given to the messages sent with this context
+    ttl: Option<Duration>,
+    // a message still queued past its deadline is discarded as expired
+    deadline: Option<Instant>,
+}
+
+impl Context {
+    pub fn new(parent: Option<Arc<Context>>) -> Arc<Self> {
+        Arc::new(Self {
+            headers: parent.as_ref().and_then(|p| p.headers.clone()),
+            scope: parent.as_ref().and_then(|p| p.scope.clone()),
+            ttl: parent.as_ref().and_then(|p| p.ttl),
+            parent,
+            cancelled: AtomicBool::new(false),
+            tracked: false,
+            single: false,
+            picked: AtomicBool::new(false),
+            deadline: None,
+        })
+    }
+
+    pub fn with_scope(parent: Option<Arc<Context>>, scope: Arc<ScopeState>) -> Arc<Self> {
+        Arc::new(Self {
+            headers: parent.as_ref().and_then(|p| p.headers.clone()),
+            scope: Some(scope),
+            ttl: parent.as_ref().and_then(|p| p.ttl),
+            parent,
+            cancelled: AtomicBool::new(false),
+            tracked: false,
+            single: false,
+            picked: AtomicBool::new(false),
+            deadline: None,
+        })
+    }
+
+    // context for a message sent from `parent`, tracked if it is in a scope;
+    // with `single` set or a `deadline` the message always gets a context of
+    // its own
+    pub fn for_message(
+        parent: Option<Arc<Context>>,
+        single: bool,
+        deadline: Option<Instant>,
+    ) -> Option<Arc<Self>> {
+        let scope = parent.as_ref().and_then(|p| p.scope.clone());
+        if scope.is_none() && !single && deadline.is_none() {
+            return parent;
+        }
+
+        // a message's own context is never cancelled by itself, so messages
+        // sent while handling it hang off its parent instead and the chain
+        // does not grow with every hop
+        let parent = match parent {
+            Some(p) if p.single => p.parent.clone(),
+            parent => parent,
+        };
+
+        if let Some(scope) = &scope {
+            scope.start();
+        }
+
+        Some(Arc::new(Self {
+            headers: parent.as_ref().and_then(|p| p.headers.clone()),
+            tracked: scope.is_some(),
+            scope,
+            parent,
+            cancelled: AtomicBool::new(false),
+            single: true,
+            picked: AtomicBool::new(false),
+            ttl: None,
+            deadline,
+        }))
+    }
+
+    pub fn with_ttl(parent: Option<Arc<Context>>, ttl: Duration) -> Arc<Self> {
+        Arc::new(Self {
+            headers: parent.as_ref().and_then(|p| p.headers.clone()),
+            scope: parent.as_ref().and_then(|p| p.scope.clone()),
+            parent,
+            cancelled: AtomicBool::new(false),
+            tracked: false,
+            single: false,
+            picked: AtomicBool::new(false),
+            ttl: Some(ttl),
+            deadline: None,
+        })
+    }
+
+    pub fn with_headers(parent: Option<Arc<Context>>, headers: Headers) -> Arc<Self> {
+        let headers = match parent.as_ref().and_then(|p| p.headers.as_deref()) {
+            Some(inherited) => {
+                let mut merged = inherited.clone();
+                merged.extend(headers.iter());
+                merged
+            }
+            None => headers,
+        };
+
+        Arc::new(Self {
+            scope: parent.as_ref().and_then(|p| p.scope.clone()),
+            ttl: parent.as_ref().and_then(|p| p.ttl),
+            parent,
+            headers: Some(Arc::new(headers)),
+            cancelled: AtomicBool::new(false),
+            tracked: false,
+            single: false,
+            picked: AtomicBool::new(false),
+            deadline: None,
+        })
+    }
+
+    #[inline]
+    pub fn headers(&self) -> Option<&Headers> {
+        self.headers.as_deref()
+    }
+
+    #[inline]
+    pub fn scope(&self) -> Option<Arc<ScopeState>> {
+        self.scope.clone()
+    }
+
+    #[inline]
+    pub fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    // deadline of the message handled with this context, if any
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+            .or_else(|| self.parent.as_ref().and_then(|p| p.deadline()))
+    }
+
+    #[inline]
+    pub fn is_expired(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    #[inline]
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+            || self.parent.as_ref().is_some_and(|p| p.is_cancelled())
+    }
+
+    // neither cancelled nor past its deadline
+    #[inline]
+    pub fn is_live(&self) -> bool {
+        !self.is_cancelled() && !self.is_expired()
+    }
+
+    // called by the receiver when it picks the message up; false if the
+    // message was evicted or cancelled while queued
+    pub fn start(&self) -> bool {
+        if self.single && self.picked.swap(true, Ordering::SeqCst) {
+            return false;
+        }
+
+        !self.is_cancelled()
+    }
+
+    // takes a queued message away from its receiver; false if it was
+    // already picked up
+    pub fn evict(&self) -> bool {
+        self.single && !self.picked.swap(true, Ordering::SeqCst)
+    }
+
+    #[inline]
+    pub fn is_picked(&self) -> bool {
+        self.picked.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for Context {
+    fn drop(&mut self) {
+        if self.tracked {
+            if let Some(scope) = &self.scope {
+                scope.done();
+            }
+        }
+    }
+}
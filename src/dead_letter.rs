@@ -0,0 +1,171 @@
+use core::{any::Any, future::Future, sync::atomic::Ordering};
+use std::{alloc::Layout, borrow::Cow, sync::Arc};
+
+use crate::{
+    envelop::{SharedMessage, TypeTag, TypeTagged},
+    error::{GenericError, StdSyncSendError},
+    Bus, Message, SendOptions,
+};
+
+// Republished for a message whose handler has failed for good; subscribe to
+// `DeadLetter<M>` to receive the failed `M`s
+#[derive(Debug)]
+pub struct DeadLetter<M: Message> {
+    pub message: M,
+    pub error: GenericError,
+    pub receiver: &'static str,
+    pub attempts: u32,
+}
+
+impl<M: Message> TypeTagged for DeadLetter<M> {
+    fn type_tag_() -> TypeTag {
+        format!("messagebus::DeadLetter<{}>", M::type_tag_()).into()
+    }
+
+    fn type_tag(&self) -> TypeTag {
+        Self::type_tag_()
+    }
+
+    fn type_name(&self) -> Cow<'_, str> {
+        Self::type_tag_()
+    }
+
+    fn type_layout(&self) -> Layout {
+        Layout::for_value(self)
+    }
+}
+
+impl<M: Message> Message for DeadLetter<M> {
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+    fn as_any_boxed(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+    fn as_any_arc(self: Arc<Self>) -> Arc<dyn Any> {
+        self
+    }
+
+    fn as_shared_ref(&self) -> Option<&dyn SharedMessage> {
+        None
+    }
+    fn as_shared_mut(&mut self) -> Option<&mut dyn SharedMessage> {
+        None
+    }
+    fn as_shared_boxed(self: Box<Self>) -> Result<Box<dyn SharedMessage>, Box<dyn Message>> {
+        Err(self)
+    }
+    fn as_shared_arc(self: Arc<Self>) -> Option<Arc<dyn SharedMessage>> {
+        None
+    }
+
+    fn try_clone_into(&self, into: &mut dyn Any) -> bool {
+        let into = if let Some(inner) = into.downcast_mut::<Option<Self>>() {
+            inner
+        } else {
+            return false;
+        };
+
+        if let Some(cloned) = self.try_clone() {
+            into.replace(cloned);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn try_clone_boxed(&self) -> Option<Box<dyn Message>> {
+        self.try_clone().map(|x| Box::new(x) as _)
+    }
+
+    fn try_clone(&self) -> Option<Self> {
+        Some(DeadLetter {
+            message: self.message.try_clone()?,
+            error: self.error.clone(),
+            receiver: self.receiver,
+            attempts: self.attempts,
+        })
+    }
+}
+
+impl Bus {
+    // whether a failed message is worth keeping a copy of for dead-lettering
+    pub(crate) fn wants_dead_letters<M: Message>(&self) -> bool {
+        self.inner
+            .routes
+            .load()
+            .lookup
+            .contains_key(&(DeadLetter::<M>::type_tag_(), None, None))
+    }
+
+    // counts the dead letters as pending right away, so a flush waits for
+    // them even once the handler gave its slot back
+    pub(crate) fn send_dead_letters<'a, T, M, E>(
+        &'a self,
+        msgs: impl IntoIterator<Item = M> + 'a,
+        err: &E,
+        attempts: u32,
+    ) -> impl Future<Output = ()> + 'a
+    where
+        M: Message,
+        E: StdSyncSendError,
+    {
+        let error = GenericError::from_err(err.type_tag(), err);
+        let pending = DeadLettersPending::new(self);
+
+        async move {
+            // sent like any other message: middleware, dedup and the closed
+            // bus apply to dead letters too
+            for message in msgs {
+                let dl = DeadLetter {
+                    message,
+                    error: error.clone(),
+                    receiver: std::any::type_name::<T>(),
+                    attempts,
+                };
+
+                if let Err(err) = pending
+                    .0
+                    .send_cloned(dl, SendOptions::Broadcast, Message::try_clone)
+                    .await
+                {
+                    warn!("Dead letter dropped: {}", err);
+                }
+            }
+        }
+    }
+
+    pub(crate) async fn dead_letters_idle(&self) {
+        loop {
+            let notified = self.inner.dead_lettered.notified();
+            futures::pin_mut!(notified);
+            notified.as_mut().enable();
+
+            if self.inner.dead_letters.load(Ordering::SeqCst) == 0 {
+                return;
+            }
+
+            notified.await;
+        }
+    }
+}
+
+struct DeadLettersPending<'a>(&'a Bus);
+
+impl<'a> DeadLettersPending<'a> {
+    fn new(bus: &'a Bus) -> Self {
+        bus.inner.dead_letters.fetch_add(1, Ordering::SeqCst);
+        Self(bus)
+    }
+}
+
+impl Drop for DeadLettersPending<'_> {
+    fn drop(&mut self) {
+        if self.0.inner.dead_letters.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.inner.dead_lettered.notify_waiters();
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use core::{
+    any::Any,
+    hash::Hash,
+    marker::PhantomData,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+use std::collections::{HashSet, VecDeque};
+
+use parking_lot::Mutex;
+use tokio::time::Instant;
+
+use crate::{envelop::TypeTag, stats::Stats, Bus, Message};
+
+// Messages registered with `BusBuilder::deduplicate` are dropped on send when
+// another message with the same key was sent within the window. Requests are
+// never filtered, as the requester still waits for an answer.
+pub trait Deduplicate: Message {
+    type Key: Hash + Eq + Clone + Send + 'static;
+
+    fn idempotency_key(&self) -> Self::Key;
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum DedupWindow {
+    // remembers keys for the given time
+    Time(Duration),
+    // remembers the given number of the most recent keys
+    Size(usize),
+}
+
+pub(crate) trait DedupFilter: Send + Sync {
+    // records the key of `msg` and returns it, `None` for a duplicate
+    fn insert(&self, msg: &dyn Message) -> Option<Box<dyn Any + Send>>;
+    fn forget(&self, key: Box<dyn Any + Send>);
+    fn stats(&self) -> Stats;
+}
+
+struct Seen<K> {
+    keys: HashSet<K>,
+    order: VecDeque<(K, Instant)>,
+}
+
+pub(crate) struct Dedup<M: Deduplicate> {
+    window: DedupWindow,
+    seen: Mutex<Seen<M::Key>>,
+    hits: AtomicU64,
+    _m: PhantomData<fn(M)>,
+}
+
+impl<M: Deduplicate> Dedup<M> {
+    pub fn new(window: DedupWindow) -> Self {
+        Self {
+            window,
+            seen: Mutex::new(Seen {
+                keys: HashSet::new(),
+                order: VecDeque::new(),
+            }),
+            hits: AtomicU64::new(0),
+            _m: Default::default(),
+        }
+    }
+}
+
+impl<M: Deduplicate> DedupFilter for Dedup<M> {
+    fn insert(&self, msg: &dyn Message) -> Option<Box<dyn Any + Send>> {
+        let key = match msg.as_any_ref().downcast_ref::<M>() {
+            Some(msg) => msg.idempotency_key(),
+            None => return Some(Box::new(())),
+        };
+
+        let now = Instant::now();
+        let mut seen = self.seen.lock();
+
+        if let DedupWindow::Time(ttl) = self.window {
+            while let Some((k, _)) = seen
+                .order
+                .front()
+                .filter(|(_, at)| now.duration_since(*at) >= ttl)
+                .cloned()
+            {
+                seen.order.pop_front();
+                seen.keys.remove(&k);
+            }
+        }
+
+        if seen.keys.contains(&key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+
+        seen.keys.insert(key.clone());
+        seen.order.push_back((key.clone(), now));
+
+        if let DedupWindow::Size(size) = self.window {
+            while seen.order.len() > size {
+                if let Some((k, _)) = seen.order.pop_front() {
+                    seen.keys.remove(&k);
+                }
+            }
+        }
+
+        Some(Box::new(key))
+    }
+
+    fn forget(&self, key: Box<dyn Any + Send>) {
+        let key = match key.downcast::<M::Key>() {
+            Ok(key) => key,
+            Err(_) => return,
+        };
+
+        let mut seen = self.seen.lock();
+        if seen.keys.remove(&*key) {
+            if let Some(pos) = seen.order.iter().rposition(|(k, _)| *k == *key) {
+                seen.order.remove(pos);
+            }
+        }
+    }
+
+    fn stats(&self) -> Stats {
+        Stats {
+            msg_type_tag: M::type_tag_(),
+            has_dedup: true,
+            dedup_size: self.seen.lock().order.len() as _,
+            dedup_hits: self.hits.load(Ordering::Relaxed) as _,
+            ..Default::default()
+        }
+    }
+}
+
+// The key of a message that passed the filter. It is forgotten again unless
+// `keep` is called once the message is sent, so a message whose send failed
+// (full receiver, open circuit, ...) is not dropped as a duplicate on retry.
+pub(crate) struct DedupKey<'a>(Option<(&'a dyn DedupFilter, Box<dyn Any + Send>)>);
+
+impl DedupKey<'_> {
+    pub fn keep(mut self) {
+        self.0 = None;
+    }
+
+    // keeps the key if the send succeeded
+    pub fn settle<T, E>(self, res: Result<T, E>) -> Result<T, E> {
+        if res.is_ok() {
+            self.keep();
+        }
+
+        res
+    }
+}
+
+impl Drop for DedupKey<'_> {
+    fn drop(&mut self) {
+        if let Some((filter, key)) = self.0.take() {
+            filter.forget(key);
+        }
+    }
+}
+
+impl Bus {
+    // `None` for a duplicate message, which is dropped
+    pub(crate) fn dedup(&self, msg: &dyn Message) -> Option<DedupKey<'_>> {
+        if self.inner.dedup.is_empty() {
+            return Some(DedupKey(None));
+        }
+
+        let tt: TypeTag = msg.type_tag();
+        match self.inner.dedup.get(&tt) {
+            Some(filter) => match filter.insert(msg) {
+                Some(key) => Some(DedupKey(Some((&**filter, key)))),
+                None => {
+                    debug!("Duplicate message {} dropped", tt);
+                    None
+                }
+            },
+            None => Some(DedupKey(None)),
+        }
+    }
+}
@@ -16,7 +16,7 @@ pub trait DynError: TypeTagged {
 pub trait StdSyncSendError: std::error::Error + TypeTagged + Send + Sync + Unpin + 'static {}
 impl<T: std::error::Error + TypeTagged + Send + Sync + Unpin + 'static> StdSyncSendError for T {}
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct GenericError {
     pub type_tag: TypeTag,
     pub description: String,
@@ -113,6 +113,18 @@ pub enum Error<M: fmt::Debug + 'static = (), E: StdSyncSendError = GenericError>
     #[error("Not Ready")]
     NotReady,
 
+    #[error("Timeout")]
+    Timeout,
+
+    #[error("Circuit Open")]
+    CircuitOpen,
+
+    #[error("Cancelled")]
+    Cancelled,
+
+    #[error("Expired")]
+    Expired,
+
     #[error("Other({0})")]
     Other(E),
 
@@ -155,6 +167,10 @@ impl<M: fmt::Debug + 'static, E: StdSyncSendError> Error<M, E> {
             Error::MessageCastError => Error::MessageCastError,
             Error::TypeTagNotRegistered(tt) => Error::TypeTagNotRegistered(tt),
             Error::NotReady => Error::NotReady,
+            Error::Timeout => Error::Timeout,
+            Error::CircuitOpen => Error::CircuitOpen,
+            Error::Cancelled => Error::Cancelled,
+            Error::Expired => Error::Expired,
             Error::Unknown(msg) => Error::Unknown(msg),
         }
     }
@@ -173,6 +189,10 @@ impl<M: fmt::Debug + 'static, E: StdSyncSendError> Error<M, E> {
             Error::MessageCastError => Error::MessageCastError,
             Error::TypeTagNotRegistered(tt) => Error::TypeTagNotRegistered(tt),
             Error::NotReady => Error::NotReady,
+            Error::Timeout => Error::Timeout,
+            Error::CircuitOpen => Error::CircuitOpen,
+            Error::Cancelled => Error::Cancelled,
+            Error::Expired => Error::Expired,
             Error::Unknown(msg) => Error::Unknown(msg),
         }
     }
@@ -200,6 +220,10 @@ impl<M: Message, E: StdSyncSendError> Error<M, E> {
             Error::MessageCastError => Error::MessageCastError,
             Error::TypeTagNotRegistered(tt) => Error::TypeTagNotRegistered(tt),
             Error::NotReady => Error::NotReady,
+            Error::Timeout => Error::Timeout,
+            Error::CircuitOpen => Error::CircuitOpen,
+            Error::Cancelled => Error::Cancelled,
+            Error::Expired => Error::Expired,
             Error::Unknown(msg) => Error::Unknown(msg),
         }
     }
@@ -218,6 +242,10 @@ impl<M: Message, E: StdSyncSendError> Error<M, E> {
             Error::MessageCastError => Error::MessageCastError,
             Error::TypeTagNotRegistered(tt) => Error::TypeTagNotRegistered(tt),
             Error::NotReady => Error::NotReady,
+            Error::Timeout => Error::Timeout,
+            Error::CircuitOpen => Error::CircuitOpen,
+            Error::Cancelled => Error::Cancelled,
+            Error::Expired => Error::Expired,
             Error::Unknown(msg) => Error::Unknown(msg),
         }
     }
@@ -238,6 +266,10 @@ impl<E: StdSyncSendError> Error<(), E> {
             Error::MessageCastError => Error::MessageCastError,
             Error::TypeTagNotRegistered(tt) => Error::TypeTagNotRegistered(tt),
             Error::NotReady => Error::NotReady,
+            Error::Timeout => Error::Timeout,
+            Error::CircuitOpen => Error::CircuitOpen,
+            Error::Cancelled => Error::Cancelled,
+            Error::Expired => Error::Expired,
             Error::Unknown(msg) => Error::Unknown(msg),
         }
     }
@@ -269,6 +301,10 @@ impl Error<Box<dyn Message>> {
             Error::MessageCastError => Error::MessageCastError,
             Error::TypeTagNotRegistered(tt) => Error::TypeTagNotRegistered(tt),
             Error::NotReady => Error::NotReady,
+            Error::Timeout => Error::Timeout,
+            Error::CircuitOpen => Error::CircuitOpen,
+            Error::Cancelled => Error::Cancelled,
+            Error::Expired => Error::Expired,
             Error::Unknown(msg) => Error::Unknown(msg),
         }
     }
@@ -1,11 +1,22 @@
 mod builder;
+mod context;
+mod dead_letter;
+mod dedup;
 mod envelop;
 pub mod error;
 mod handler;
+mod headers;
+mod middleware;
+mod pipeline;
 mod receiver;
 pub mod receivers;
 mod relay;
+mod rng;
+mod scheduler;
+mod scope;
+mod shutdown;
 mod stats;
+mod timer;
 mod trait_object;
 pub mod type_tag;
 
@@ -22,33 +33,51 @@ pub mod derive {
 }
 
 // privavte
+use arc_swap::ArcSwap;
 use core::{
     any::Any,
     sync::atomic::{AtomicBool, AtomicU64, Ordering},
     time::Duration,
 };
+use futures::{stream::FuturesUnordered, Stream, StreamExt};
 use smallvec::SmallVec;
 use std::{
     collections::{HashMap, HashSet},
     sync::Arc,
 };
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, Notify};
 
 use builder::BusBuilder;
+use context::Context;
+use dashmap::DashMap;
+use dedup::DedupFilter;
 use error::{Error, SendError, StdSyncSendError};
 use receiver::{Permit, Receiver};
+use rng::Rng;
+use scheduler::{Delivery, Scheduler};
 use stats::Stats;
+use timer::Timer;
 
 // public
-pub use builder::Module;
+pub use builder::{Module, ModuleHandle};
+pub use cron;
 pub use ctor;
+pub use dead_letter::DeadLetter;
+pub use dedup::{DedupWindow, Deduplicate};
 pub use envelop::{IntoBoxedMessage, Message, MessageBounds, SharedMessage, TypeTag, TypeTagged};
 pub use handler::*;
+pub use headers::Headers;
+pub use middleware::Middleware;
+pub use pipeline::Pipeline;
 pub use receiver::{
     Action, Event, EventBoxed, ReciveTypedReceiver, ReciveUntypedReceiver, SendTypedReceiver,
     SendUntypedReceiver, TypeTagAccept, TypeTagAcceptItem,
 };
 pub use relay::Relay;
+pub use scope::Scope;
+pub use shutdown::{
+    ReceiverShutdown, ShutdownMode, ShutdownOptions, ShutdownOutcome, ShutdownReport,
+};
 pub use type_tag::{deserialize_shared_message, register_shared_message};
 pub type Untyped = Arc<dyn Any + Send + Sync>;
 
@@ -63,6 +92,9 @@ pub enum SendOptions {
     Direct(u64),
     Random,
     Balanced,
+    RoundRobin,
+    // one member of the consumer group, picked by load as with `Balanced`
+    Group(&'static str),
 }
 
 impl Default for SendOptions {
@@ -71,15 +103,13 @@ impl Default for SendOptions {
     }
 }
 
-pub struct BusInner {
+pub(crate) struct Routes {
     receivers: HashSet<Receiver>,
     lookup: HashMap<LookupQuery, SmallVec<[Receiver; 4]>>,
-    closed: AtomicBool,
-    maintain: Mutex<()>,
 }
 
-impl BusInner {
-    pub(crate) fn new(receivers: HashSet<Receiver>) -> Self {
+impl Routes {
+    fn new(receivers: HashSet<Receiver>) -> Self {
         let mut lookup = HashMap::new();
         for recv in receivers.iter() {
             for (msg, resp) in recv.iter_types() {
@@ -109,21 +139,149 @@ impl BusInner {
 
         let lookup = lookup
             .into_iter()
-            .map(|(k, v)| (k, v.into_iter().collect()))
+            .map(|(k, v)| {
+                let mut v: SmallVec<[Receiver; 4]> = v.into_iter().collect();
+                v.sort_unstable_by_key(Receiver::id);
+                (k, v)
+            })
             .collect();
 
+        Self { receivers, lookup }
+    }
+}
+
+pub struct BusInner {
+    // swapped as a whole on (un)registration, so senders never take a lock
+    routes: ArcSwap<Routes>,
+    rng: Rng,
+    round_robin: DashMap<TypeTag, AtomicU64>,
+    // set when the shutdown starts; sends are still taken until the
+    // producers are closed and `closed` is set
+    closing_flag: AtomicBool,
+    closed: AtomicBool,
+    refused: AtomicU64,
+    // set by a dropping shutdown: queued messages are skipped
+    discard: AtomicBool,
+    closing: Notify,
+    maintain: Mutex<()>,
+    middlewares: Vec<Box<dyn Middleware>>,
+    scheduler: Scheduler,
+    // dead letters not handed over to their receivers yet
+    dead_letters: AtomicU64,
+    dead_lettered: Notify,
+    timers: Vec<Arc<Timer>>,
+    dedup: HashMap<TypeTag, Box<dyn DedupFilter>>,
+    // stages of the pipelines built with `BusBuilder::pipeline`, in order,
+    // grouped by the input type of the first stage
+    pipelines: HashMap<TypeTag, Vec<Vec<Receiver>>>,
+    // default time-to-live of the messages by type
+    ttl: HashMap<TypeTag, Duration>,
+}
+
+impl BusInner {
+    pub(crate) fn new(
+        receivers: HashSet<Receiver>,
+        seed: Option<u64>,
+        middlewares: Vec<Box<dyn Middleware>>,
+        timers: Vec<Arc<Timer>>,
+        dedup: HashMap<TypeTag, Box<dyn DedupFilter>>,
+        pipelines: HashMap<TypeTag, Vec<Vec<Receiver>>>,
+        ttl: HashMap<TypeTag, Duration>,
+    ) -> Self {
         Self {
-            receivers,
-            lookup,
+            routes: ArcSwap::from_pointee(Routes::new(receivers)),
+            rng: seed.map_or_else(Rng::from_time, Rng::new),
+            round_robin: DashMap::new(),
+            closing_flag: AtomicBool::new(false),
             closed: AtomicBool::new(false),
+            refused: AtomicU64::new(0),
+            discard: AtomicBool::new(false),
+            closing: Notify::new(),
             maintain: Mutex::new(()),
+            middlewares,
+            scheduler: Scheduler::new(),
+            dead_letters: AtomicU64::new(0),
+            dead_lettered: Notify::new(),
+            timers,
+            dedup,
+            pipelines,
+            ttl,
         }
     }
+
+    pub(crate) fn add_receivers(&self, rs: &[Receiver]) {
+        self.routes.rcu(|routes| {
+            let mut receivers = routes.receivers.clone();
+            receivers.extend(rs.iter().cloned());
+            Routes::new(receivers)
+        });
+    }
+
+    pub(crate) fn remove_receivers(&self, ids: &[u64]) {
+        self.routes.rcu(|routes| {
+            let mut receivers = routes.receivers.clone();
+            receivers.retain(|r| !ids.contains(&r.id()));
+            Routes::new(receivers)
+        });
+    }
+}
+
+// Frees the waiter slot and marks the request as abandoned when the
+// requesting future is dropped before its response arrives
+struct RequestGuard<'a> {
+    receiver: &'a Receiver,
+    mid: u64,
+    context: Arc<Context>,
+    done: bool,
+}
+
+impl<'a> RequestGuard<'a> {
+    fn new(receiver: &'a Receiver, mid: u64, parent: Option<Arc<Context>>) -> Self {
+        Self {
+            receiver,
+            mid,
+            context: Context::new(parent),
+            done: false,
+        }
+    }
+}
+
+impl Drop for RequestGuard<'_> {
+    fn drop(&mut self) {
+        if !self.done {
+            self.context.cancel();
+            self.receiver.remove_response_waiter(self.mid);
+        }
+    }
+}
+
+#[inline]
+fn is_rejected<M: core::fmt::Debug>(res: &Result<(), Error<M>>) -> bool {
+    matches!(
+        res,
+        Err(Error::CircuitOpen) | Err(Error::SendError(SendError::Full(_)))
+    )
+}
+
+// other send errors are ignored on broadcast, but a message refused by every
+// receiver (by its circuit breaker or overflow policy) is reported back to the
+// sender
+#[inline]
+fn broadcast_result<M: core::fmt::Debug>(
+    rejected: bool,
+    res: Result<(), Error<M>>,
+) -> Result<(), Error<M>> {
+    if rejected && is_rejected(&res) {
+        res
+    } else {
+        Ok(())
+    }
 }
 
 #[derive(Clone)]
 pub struct Bus {
     inner: Arc<BusInner>,
+    context: Option<Arc<Context>>,
 }
 
 impl Bus {
@@ -132,36 +290,153 @@ impl Bus {
         BusBuilder::new()
     }
 
+    #[inline]
+    pub(crate) fn context(&self) -> Option<Arc<Context>> {
+        self.context.clone()
+    }
+
+    #[inline]
+    pub(crate) fn with_context(&self, context: Option<Arc<Context>>) -> Bus {
+        Bus {
+            inner: self.inner.clone(),
+            context,
+        }
+    }
+
+    pub fn headers(&self) -> Option<&Headers> {
+        self.context.as_ref().and_then(|ctx| ctx.headers())
+    }
+
+    pub fn with_headers(&self, headers: Headers) -> Bus {
+        self.with_context(Some(Context::with_headers(self.context(), headers)))
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.context.as_ref().is_some_and(|ctx| ctx.is_cancelled())
+    }
+
+    // messages sent with the returned bus are discarded as expired if they
+    // wait in a receiver queue for longer than `ttl`
+    pub fn with_ttl(&self, ttl: Duration) -> Bus {
+        self.with_context(Some(Context::with_ttl(self.context(), ttl)))
+    }
+
+    // deadline of a message of type `tt` sent now, from the bus ttl, else
+    // the deadline of the message being handled or the default of its type
+    pub(crate) fn message_deadline(&self, tt: &TypeTag) -> Option<tokio::time::Instant> {
+        let now = tokio::time::Instant::now();
+        let ctx = self.context.as_deref();
+
+        ctx.and_then(|ctx| ctx.ttl())
+            .map(|ttl| now + ttl)
+            .or_else(|| ctx.and_then(|ctx| ctx.deadline()))
+            .or_else(|| self.inner.ttl.get(tt).map(|ttl| now + *ttl))
+    }
+
+    // called by the receivers when they pick a message up; an error means
+    // the message is to be skipped, as it was cancelled, evicted from the
+    // queue, discarded by the shutdown or expired
+    pub(crate) fn start_message<E: StdSyncSendError>(&self) -> Result<(), Error<(), E>> {
+        if self.inner.discard.load(Ordering::SeqCst) {
+            return Err(Error::Cancelled);
+        }
+
+        match &self.context {
+            Some(ctx) if !ctx.start() => Err(Error::Cancelled),
+            Some(ctx) if ctx.is_expired() => Err(Error::Expired),
+            _ => Ok(()),
+        }
+    }
+
+    // `start_message` for a message picked up before, checked again once the
+    // batch it waited in is dispatched
+    pub(crate) fn recheck_message<E: StdSyncSendError>(&self) -> Result<(), Error<(), E>> {
+        if self.inner.discard.load(Ordering::SeqCst) {
+            return Err(Error::Cancelled);
+        }
+
+        match &self.context {
+            Some(ctx) if ctx.is_cancelled() => Err(Error::Cancelled),
+            Some(ctx) if ctx.is_expired() => Err(Error::Expired),
+            _ => Ok(()),
+        }
+    }
+
+    // whether the message handled with this bus is still wanted, checked
+    // before each retry
+    pub(crate) fn is_live(&self) -> bool {
+        !self.inner.discard.load(Ordering::SeqCst)
+            && self.context.as_ref().is_none_or(|ctx| ctx.is_live())
+    }
+
+    // a batch is worth retrying while any of its messages is
+    pub(crate) fn is_batch_live(&self, mids: &[(u64, bool, Option<Arc<Context>>)]) -> bool {
+        self.is_live()
+            && mids
+                .iter()
+                .any(|(_, _, ctx)| ctx.as_ref().is_none_or(|ctx| ctx.is_live()))
+    }
+
+    #[inline]
+    pub(crate) fn rng(&self) -> &Rng {
+        &self.inner.rng
+    }
+
     pub fn is_closing(&self) -> bool {
-        self.inner.closed.load(Ordering::SeqCst)
+        self.inner.closing_flag.load(Ordering::SeqCst)
+    }
+
+    // a send is refused once the shutdown got to the consumers, the refused
+    // ones are counted in its report
+    fn refuse_closed(&self) -> bool {
+        let closed = self.inner.closed.load(Ordering::SeqCst);
+        if closed {
+            self.inner.refused.fetch_add(1, Ordering::Relaxed);
+        }
+
+        closed
     }
 
     pub(crate) fn init(&self) {
-        for r in self.inner.receivers.iter() {
+        for r in self.inner.routes.load().receivers.iter() {
             r.init(self).unwrap();
         }
     }
 
     pub async fn ready(&self) {
-        for r in self.inner.receivers.iter() {
+        let routes = self.inner.routes.load_full();
+        for r in routes.receivers.iter() {
             r.ready().await;
         }
     }
 
+    #[inline]
     pub async fn close(&self) {
-        let _handle = self.inner.maintain.lock().await;
-        self.inner.closed.store(true, Ordering::SeqCst);
+        self.shutdown(Default::default()).await;
+    }
+
+    #[inline]
+    pub async fn flush_all(&self) {
+        self.flush_all_ext(false).await
+    }
+
+    // with `wait_scheduled` set, also waits for all messages scheduled with
+    // `send_after`/`send_at` to be delivered and flushed
+    pub async fn flush_all_ext(&self, wait_scheduled: bool) {
+        loop {
+            if wait_scheduled {
+                self.inner.scheduler.idle().await;
+            }
 
-        for r in self.inner.receivers.iter() {
-            let err = tokio::time::timeout(Duration::from_secs(20), r.close(self)).await;
+            self.flush_receivers().await;
 
-            if let Err(err) = err {
-                error!("Close timeout on {}: {}", r.name(), err);
+            if !wait_scheduled || self.inner.scheduler.pending() == 0 {
+                break;
             }
         }
     }
 
-    pub async fn flush_all(&self) {
+    async fn flush_receivers(&self) {
         let fuse_count = 32i32;
         let mut breaked = false;
         let mut iters = 0usize;
@@ -169,7 +444,8 @@ impl Bus {
         for _ in 0..fuse_count {
             iters += 1;
             let mut flushed = false;
-            for r in self.inner.receivers.iter() {
+            let routes = self.inner.routes.load_full();
+            for r in routes.receivers.iter() {
                 if r.need_flush() {
                     flushed = true;
 
@@ -178,8 +454,13 @@ impl Bus {
             }
 
             if !flushed {
-                breaked = true;
-                break;
+                if self.inner.dead_letters.load(Ordering::SeqCst) == 0 {
+                    breaked = true;
+                    break;
+                }
+
+                // failed handlers still publishing dead letters
+                self.dead_letters_idle().await;
             }
         }
 
@@ -265,7 +546,8 @@ impl Bus {
     }
 
     pub async fn sync_all(&self) {
-        for r in self.inner.receivers.iter() {
+        let routes = self.inner.routes.load_full();
+        for r in routes.receivers.iter() {
             r.sync(self).await;
         }
     }
@@ -291,10 +573,26 @@ impl Bus {
         }
     }
 
+    #[inline]
     pub async fn idle_all(&self) {
-        for r in self.inner.receivers.iter() {
-            r.flush(self).await;
-            r.idle().await;
+        self.idle_all_ext(false).await
+    }
+
+    pub async fn idle_all_ext(&self, wait_scheduled: bool) {
+        loop {
+            if wait_scheduled {
+                self.inner.scheduler.idle().await;
+            }
+
+            let routes = self.inner.routes.load_full();
+            for r in routes.receivers.iter() {
+                r.flush(self).await;
+                r.idle().await;
+            }
+
+            if !wait_scheduled || self.inner.scheduler.pending() == 0 {
+                break;
+            }
         }
     }
 
@@ -375,17 +673,28 @@ impl Bus {
     pub fn try_send_ext<M: Message + Clone>(
         &self,
         msg: M,
-        _options: SendOptions,
+        options: SendOptions,
     ) -> core::result::Result<(), Error<M>> {
-        if self.inner.closed.load(Ordering::SeqCst) {
+        if self.refuse_closed() {
             return Err(SendError::Closed(msg).into());
         }
 
+        let msg = self.try_before_send(msg)?;
+
+        let key = match self.dedup(&msg) {
+            Some(key) => key,
+            None => return Ok(()),
+        };
+
         let tt = msg.type_tag();
         let mid = ID_COUNTER.fetch_add(1, Ordering::Relaxed);
 
-        if let Some(rs) = self.inner.lookup.get(&(msg.type_tag(), None, None)) {
-            let permits = if let Some(x) = self.try_reserve(&tt, rs) {
+        let rs = self
+            .select_receivers(tt.clone(), options, None, None, false)
+            .collect::<SmallVec<[Receiver; 4]>>();
+
+        if !rs.is_empty() {
+            let permits = if let Some(x) = self.try_reserve(&tt, &rs) {
                 x
             } else {
                 return Err(SendError::Full(msg).into());
@@ -394,17 +703,18 @@ impl Bus {
             let mut iter = permits.into_iter().zip(rs.iter());
             let mut counter = 1;
             let total = rs.len();
+            let mut rejected = true;
 
             while counter < total {
                 let (p, r) = iter.next().unwrap();
-                let _ = r.send(self, mid, msg.clone(), false, p);
+                rejected &= is_rejected(&r.send(self, mid, msg.clone(), false, p));
 
                 counter += 1;
             }
 
             if let Some((p, r)) = iter.next() {
-                let _ = r.send(self, mid, msg, false, p);
-                return Ok(());
+                let res = r.send(self, mid, msg, false, p);
+                return key.settle(broadcast_result(rejected, res));
             }
         }
 
@@ -435,27 +745,77 @@ impl Bus {
         Ok(self.send_ext(msg, SendOptions::Broadcast).await?)
     }
 
+    #[inline]
+    pub async fn send_with_ttl<M: Message + Clone>(
+        &self,
+        msg: M,
+        ttl: Duration,
+    ) -> core::result::Result<(), Error<M>> {
+        self.with_ttl(ttl)
+            .send_ext(msg, SendOptions::Broadcast)
+            .await
+    }
+
+    #[inline]
+    pub async fn send_with_headers<M: Message + Clone>(
+        &self,
+        msg: M,
+        headers: Headers,
+    ) -> core::result::Result<(), Error<M>> {
+        self.with_headers(headers)
+            .send_ext(msg, SendOptions::Broadcast)
+            .await
+    }
+
+    #[inline]
     pub async fn send_ext<M: Message + Clone>(
         &self,
         msg: M,
-        _options: SendOptions,
+        options: SendOptions,
+    ) -> core::result::Result<(), Error<M>> {
+        self.send_cloned(msg, options, |msg| Some(msg.clone()))
+            .await
+    }
+
+    // `send_ext` for messages that may not be cloneable: the receivers a
+    // copy could not be made for are skipped
+    pub(crate) async fn send_cloned<M: Message>(
+        &self,
+        mut msg: M,
+        options: SendOptions,
+        clone: fn(&M) -> Option<M>,
     ) -> core::result::Result<(), Error<M>> {
-        if self.inner.closed.load(Ordering::SeqCst) {
+        if self.refuse_closed() {
             return Err(SendError::Closed(msg).into());
         }
 
+        self.before_send(&mut msg)
+            .await
+            .map_err(Error::OtherBoxed)?;
+
+        let key = match self.dedup(&msg) {
+            Some(key) => key,
+            None => return Ok(()),
+        };
+
         let tt = msg.type_tag();
         let mid = ID_COUNTER.fetch_add(1, Ordering::Relaxed);
 
-        if let Some(rs) = self.inner.lookup.get(&(msg.type_tag(), None, None)) {
-            if let Some((last, head)) = rs.split_last() {
-                for r in head {
-                    let _ = r.send(self, mid, msg.clone(), false, r.reserve(&tt).await);
-                }
+        let mut iter = self
+            .select_receivers(tt.clone(), options, None, None, false)
+            .peekable();
 
-                let _ = last.send(self, mid, msg, false, last.reserve(&tt).await);
+        let mut rejected = true;
+        while let Some(r) = iter.next() {
+            if iter.peek().is_none() {
+                let permit = r.reserve(&tt).await;
+                let res = r.send(self, mid, msg, false, permit);
+                return key.settle(broadcast_result(rejected, res));
+            }
 
-                return Ok(());
+            if let Some(copy) = clone(&msg) {
+                let permit = r.reserve(&tt).await;
+                rejected &= is_rejected(&r.send(self, mid, copy, false, permit));
             }
         }
 
@@ -467,6 +827,42 @@ impl Bus {
         Ok(())
     }
 
+    #[inline]
+    pub fn send_after<M: Message + Clone>(&self, delay: Duration, msg: M) -> Result<(), Error<M>> {
+        self.send_at(tokio::time::Instant::now() + delay, msg)
+    }
+
+    pub fn send_at<M: Message + Clone>(
+        &self,
+        at: tokio::time::Instant,
+        msg: M,
+    ) -> Result<(), Error<M>> {
+        if self.refuse_closed() {
+            return Err(SendError::Closed(msg).into());
+        }
+
+        let delivery = Delivery {
+            msg: Box::new(msg),
+            context: self.context(),
+            send: |bus, msg| {
+                Box::pin(async move {
+                    let msg = *msg.as_any_boxed().downcast::<M>().unwrap();
+                    if let Err(err) = bus.send(msg).await {
+                        error!("Scheduled delivery error: {}", err);
+                    }
+                })
+            },
+        };
+
+        self.inner
+            .scheduler
+            .schedule(self, at, delivery)
+            .map_err(|delivery| {
+                let msg = *delivery.msg.as_any_boxed().downcast::<M>().unwrap();
+                SendError::Closed(msg).into()
+            })
+    }
+
     #[inline]
     pub fn force_send<M: Message + Clone>(&self, msg: M) -> Result<(), Error<M>> {
         self.force_send_ext(msg, SendOptions::Broadcast)
@@ -475,24 +871,33 @@ impl Bus {
     pub fn force_send_ext<M: Message + Clone>(
         &self,
         msg: M,
-        _options: SendOptions,
+        options: SendOptions,
     ) -> core::result::Result<(), Error<M>> {
-        if self.inner.closed.load(Ordering::SeqCst) {
+        if self.refuse_closed() {
             return Err(SendError::Closed(msg).into());
         }
 
-        let mid = ID_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let msg = self.try_before_send(msg)?;
 
-        if let Some(rs) = self.inner.lookup.get(&(msg.type_tag(), None, None)) {
-            if let Some((last, head)) = rs.split_last() {
-                for r in head {
-                    let _ = r.force_send(self, mid, msg.clone(), false);
-                }
+        let key = match self.dedup(&msg) {
+            Some(key) => key,
+            None => return Ok(()),
+        };
 
-                let _ = last.force_send(self, mid, msg, false);
+        let mid = ID_COUNTER.fetch_add(1, Ordering::Relaxed);
 
-                return Ok(());
+        let mut iter = self
+            .select_receivers(msg.type_tag(), options, None, None, false)
+            .peekable();
+
+        let mut rejected = true;
+        while let Some(r) = iter.next() {
+            if iter.peek().is_none() {
+                let res = r.force_send(self, mid, msg, false);
+                return key.settle(broadcast_result(rejected, res));
             }
+
+            rejected &= is_rejected(&r.force_send(self, mid, msg.clone(), false));
         }
 
         warn!(
@@ -505,46 +910,68 @@ impl Bus {
 
     #[inline]
     pub fn try_send_one<M: Message>(&self, msg: M) -> Result<(), Error<M>> {
-        if self.inner.closed.load(Ordering::SeqCst) {
+        if self.refuse_closed() {
             return Err(SendError::Closed(msg).into());
         }
 
+        let msg = self.try_before_send(msg)?;
+
+        let key = match self.dedup(&msg) {
+            Some(key) => key,
+            None => return Ok(()),
+        };
+
         let tt = msg.type_tag();
         let mid = ID_COUNTER.fetch_add(1, Ordering::Relaxed);
 
-        if let Some(rs) = self
+        let rs = self
             .inner
+            .routes
+            .load()
             .lookup
             .get(&(msg.type_tag(), None, None))
-            .and_then(|rs| rs.first())
-        {
+            .and_then(|rs| rs.first().cloned());
+
+        if let Some(rs) = rs {
             let permits = if let Some(x) = rs.try_reserve(&tt) {
                 x
             } else {
                 return Err(SendError::Full(msg).into());
             };
 
-            Ok(rs.send(self, mid, msg, false, permits)?)
+            key.settle(rs.send(self, mid, msg, false, permits))
         } else {
             Err(Error::NoReceivers)
         }
     }
 
-    pub async fn send_one<M: Message>(&self, msg: M) -> Result<(), Error<M>> {
-        if self.inner.closed.load(Ordering::SeqCst) {
+    pub async fn send_one<M: Message>(&self, mut msg: M) -> Result<(), Error<M>> {
+        if self.refuse_closed() {
             return Err(SendError::Closed(msg).into());
         }
 
+        self.before_send(&mut msg)
+            .await
+            .map_err(Error::OtherBoxed)?;
+
+        let key = match self.dedup(&msg) {
+            Some(key) => key,
+            None => return Ok(()),
+        };
+
         let tt = msg.type_tag();
         let mid = ID_COUNTER.fetch_add(1, Ordering::Relaxed);
 
-        if let Some(rs) = self
+        let rs = self
             .inner
+            .routes
+            .load()
             .lookup
             .get(&(msg.type_tag(), None, None))
-            .and_then(|rs| rs.first())
-        {
-            Ok(rs.send(self, mid, msg, false, rs.reserve(&tt).await)?)
+            .and_then(|rs| rs.first().cloned());
+
+        if let Some(rs) = rs {
+            key.settle(rs.send(self, mid, msg, false, rs.reserve(&tt).await))
         } else {
             Err(Error::NoReceivers)
         }
@@ -557,46 +984,224 @@ impl Bus {
 
     pub async fn request<M: Message, R: Message>(
         &self,
-        req: M,
+        mut req: M,
         options: SendOptions,
     ) -> Result<R, Error<M>> {
+        if self.refuse_closed() {
+            return Err(SendError::Closed(req).into());
+        }
+
         let tid = M::type_tag_();
         let rid = R::type_tag_();
 
-        let mut iter = self.select_receivers(tid.clone(), options, Some(rid), None, true);
+        let mut iter = self.select_receivers(tid, options, Some(rid), None, true);
         if let Some(rc) = iter.next() {
-            let (mid, rx) = rc
-                .add_response_waiter::<R>()
-                .map_err(|x| x.specify::<M>())?;
+            self.before_send(&mut req)
+                .await
+                .map_err(Error::OtherBoxed)?;
 
-            let mid = mid | 1 << (u64::BITS - 1);
-
-            rc.send(self, mid, req, true, rc.reserve(&tid).await)?;
-            rx.await.map_err(|x| x.specify::<M>())
+            self.request_receiver(&rc, req).await
         } else {
             Err(Error::NoReceivers)
         }
     }
 
-    pub async fn request_we<M, R, E>(&self, req: M, options: SendOptions) -> Result<R, Error<M, E>>
+    async fn request_receiver<M: Message, R: Message>(
+        &self,
+        rc: &Receiver,
+        req: M,
+    ) -> Result<R, Error<M>> {
+        let (mid, rx) = rc
+            .add_response_waiter::<R>()
+            .map_err(|x| x.specify::<M>())?;
+
+        let mut guard = RequestGuard::new(rc, mid, self.context());
+        let bus = self.with_context(Some(guard.context.clone()));
+        let mid = mid | 1 << (u64::BITS - 1);
+
+        rc.send(&bus, mid, req, true, rc.reserve(&M::type_tag_()).await)?;
+        let resp = rx.await;
+        guard.done = true;
+
+        resp.map_err(|x| x.specify::<M>())
+    }
+
+    pub fn request_all<M: Message + Clone, R: Message>(
+        &self,
+        req: M,
+        options: SendOptions,
+    ) -> impl Stream<Item = (u64, Result<R, Error>)> + '_ {
+        self.request_fan_out(req, options).1
+    }
+
+    // the number of receivers asked and the stream of their responses
+    fn request_fan_out<M: Message + Clone, R: Message>(
+        &self,
+        req: M,
+        options: SendOptions,
+    ) -> (usize, impl Stream<Item = (u64, Result<R, Error>)> + '_) {
+        let tid = M::type_tag_();
+        let rid = R::type_tag_();
+
+        let receivers = self
+            .select_receivers(tid, options, Some(rid), None, true)
+            .collect::<SmallVec<[Receiver; 4]>>();
+
+        let count = receivers.len();
+        let request = move |rc: Receiver, req: M| async move {
+            let resp = self.request_receiver(&rc, req).await;
+
+            (rc.id(), resp.map_err(|err| err.map_msg(|_| ())))
+        };
+
+        // the middleware sees the request once, not once per receiver
+        let fan_out = async move {
+            let mut req = req;
+            let mut rejected = Vec::new();
+            let pending = FuturesUnordered::new();
+
+            if self.refuse_closed() {
+                let closed = || Err(Error::SendError(SendError::Closed(())));
+                rejected.extend(receivers.iter().map(|rc| (rc.id(), closed())));
+            } else {
+                match self.before_send(&mut req).await {
+                    Ok(()) => {
+                        if let Some((last, rest)) = receivers.split_last() {
+                            for rc in rest {
+                                pending.push(request(rc.clone(), req.clone()));
+                            }
+
+                            pending.push(request(last.clone(), req));
+                        }
+                    }
+
+                    Err(err) => {
+                        let err = error::GenericError::from_err(err.type_tag(), &err);
+
+                        rejected.extend(
+                            receivers
+                                .iter()
+                                .map(|rc| (rc.id(), Err(Error::Other(err.clone())))),
+                        );
+                    }
+                }
+            }
+
+            futures::stream::iter(rejected).chain(pending)
+        };
+
+        (count, futures::stream::once(fan_out).flatten())
+    }
+
+    // the first `quorum` successful responses; fails with the last error as
+    // soon as too few receivers are left to reach the quorum
+    pub async fn request_quorum<M: Message + Clone, R: Message>(
+        &self,
+        req: M,
+        options: SendOptions,
+        quorum: usize,
+    ) -> Result<Vec<(u64, R)>, Error> {
+        let (mut left, resps) = self.request_fan_out(req, options);
+        futures::pin_mut!(resps);
+
+        let mut oks = Vec::with_capacity(quorum);
+        let mut last_err = Error::NoReceivers;
+
+        while oks.len() < quorum {
+            if oks.len() + left < quorum {
+                return Err(last_err);
+            }
+
+            let (id, resp) = match resps.next().await {
+                Some(item) => item,
+                None => return Err(last_err),
+            };
+
+            left -= 1;
+            match resp {
+                Ok(resp) => oks.push((id, resp)),
+                Err(err) => last_err = err,
+            }
+        }
+
+        Ok(oks)
+    }
+
+    pub async fn request_all_fold<M, R, T, F>(
+        &self,
+        req: M,
+        options: SendOptions,
+        init: T,
+        mut f: F,
+    ) -> T
+    where
+        M: Message + Clone,
+        R: Message,
+        F: FnMut(T, u64, Result<R, Error>) -> T,
+    {
+        self.request_all(req, options)
+            .fold(init, |acc, (id, resp)| {
+                futures::future::ready(f(acc, id, resp))
+            })
+            .await
+    }
+
+    #[inline]
+    pub async fn request_timeout<M: Message, R: Message>(
+        &self,
+        req: M,
+        options: SendOptions,
+        timeout: Duration,
+    ) -> Result<R, Error<M>> {
+        self.request_with_deadline(req, options, tokio::time::Instant::now() + timeout)
+            .await
+    }
+
+    pub async fn request_with_deadline<M: Message, R: Message>(
+        &self,
+        req: M,
+        options: SendOptions,
+        deadline: tokio::time::Instant,
+    ) -> Result<R, Error<M>> {
+        tokio::time::timeout_at(deadline, self.request(req, options))
+            .await
+            .unwrap_or(Err(Error::Timeout))
+    }
+
+    pub async fn request_we<M, R, E>(
+        &self,
+        mut req: M,
+        options: SendOptions,
+    ) -> Result<R, Error<M, E>>
     where
         M: Message,
         R: Message,
         E: StdSyncSendError,
     {
+        if self.refuse_closed() {
+            return Err(SendError::Closed(req).into());
+        }
+
         let tid = M::type_tag_();
         let rid = R::type_tag_();
         let eid = E::type_tag_();
 
         let mut iter = self.select_receivers(tid.clone(), options, Some(rid), Some(eid), true);
         if let Some(rc) = iter.next() {
+            self.before_send(&mut req)
+                .await
+                .map_err(Error::OtherBoxed)?;
+
             let (mid, rx) = rc.add_response_waiter_we::<R, E>().map_err(|x| {
                 x.map_err(|_| unimplemented!())
                     .map_msg(|_| unimplemented!())
             })?;
 
+            let mut guard = RequestGuard::new(&rc, mid, self.context());
+            let bus = self.with_context(Some(guard.context.clone()));
+
             rc.send(
-                self,
+                &bus,
                 mid | 1 << (u64::BITS - 1),
                 req,
                 true,
@@ -604,67 +1209,114 @@ impl Bus {
             )
             .map_err(|x| x.map_err(|_| unimplemented!()))?;
 
-            rx.await.map_err(|x| x.specify::<M>())
+            let resp = rx.await;
+            guard.done = true;
+
+            resp.map_err(|x| x.specify::<M>())
         } else {
             Err(Error::NoReceivers)
         }
     }
 
+    #[inline]
+    pub async fn request_we_timeout<M, R, E>(
+        &self,
+        req: M,
+        options: SendOptions,
+        timeout: Duration,
+    ) -> Result<R, Error<M, E>>
+    where
+        M: Message,
+        R: Message,
+        E: StdSyncSendError,
+    {
+        self.request_we_with_deadline(req, options, tokio::time::Instant::now() + timeout)
+            .await
+    }
+
+    pub async fn request_we_with_deadline<M, R, E>(
+        &self,
+        req: M,
+        options: SendOptions,
+        deadline: tokio::time::Instant,
+    ) -> Result<R, Error<M, E>>
+    where
+        M: Message,
+        R: Message,
+        E: StdSyncSendError,
+    {
+        tokio::time::timeout_at(deadline, self.request_we(req, options))
+            .await
+            .unwrap_or(Err(Error::Timeout))
+    }
+
     pub async fn send_boxed(
         &self,
-        msg: Box<dyn Message>,
+        mut msg: Box<dyn Message>,
         options: SendOptions,
     ) -> Result<(), Error<Box<dyn Message>>> {
-        if self.inner.closed.load(Ordering::SeqCst) {
+        if self.refuse_closed() {
             return Err(SendError::Closed(msg).into());
         }
 
+        self.before_send(&mut *msg)
+            .await
+            .map_err(Error::OtherBoxed)?;
+
+        let key = match self.dedup(&*msg) {
+            Some(key) => key,
+            None => return Ok(()),
+        };
+
         let tt = msg.type_tag();
         let mid = ID_COUNTER.fetch_add(1, Ordering::Relaxed);
 
-        let mut iter = self.select_receivers(tt.clone(), options, None, None, false);
-        let first = iter.next();
-
-        for r in iter {
-            let _ = r.send_boxed(
-                self,
-                mid,
-                msg.try_clone_boxed().unwrap(),
-                false,
-                r.reserve(&tt).await,
-            );
-        }
+        let mut iter = self
+            .select_receivers(tt.clone(), options, None, None, false)
+            .peekable();
 
-        if let Some(r) = first {
-            let _ = r.send_boxed(
-                self,
-                mid,
-                msg.try_clone_boxed().unwrap(),
-                false,
-                r.reserve(&tt).await,
-            );
-        } else {
-            warn!("Unhandled message: no receivers");
+        let mut rejected = true;
+        while let Some(r) = iter.next() {
+            let permit = r.reserve(&tt).await;
+
+            if iter.peek().is_none() {
+                let res = r.send_boxed(self, mid, msg, false, permit);
+                return key.settle(broadcast_result(rejected, res));
+            }
+
+            let res = r.send_boxed(self, mid, msg.try_clone_boxed().unwrap(), false, permit);
+            rejected &= is_rejected(&res);
         }
 
+        warn!("Unhandled message: no receivers");
+
         Ok(())
     }
 
     pub async fn send_boxed_one(
         &self,
-        msg: Box<dyn Message>,
+        mut msg: Box<dyn Message>,
         options: SendOptions,
     ) -> Result<(), Error<Box<dyn Message>>> {
-        if self.inner.closed.load(Ordering::SeqCst) {
+        if self.refuse_closed() {
             return Err(SendError::Closed(msg).into());
         }
 
+        self.before_send(&mut *msg)
+            .await
+            .map_err(Error::OtherBoxed)?;
+
+        let key = match self.dedup(&*msg) {
+            Some(key) => key,
+            None => return Ok(()),
+        };
+
         let tt = msg.type_tag();
         let mid = ID_COUNTER.fetch_add(1, Ordering::Relaxed);
 
         let mut iter = self.select_receivers(tt.clone(), options, None, None, false);
         if let Some(rs) = iter.next() {
-            Ok(rs.send_boxed(self, mid, msg, false, rs.reserve(&tt).await)?)
+            key.settle(rs.send_boxed(self, mid, msg, false, rs.reserve(&tt).await))
         } else {
             Err(Error::NoReceivers)
         }
@@ -672,13 +1324,17 @@ impl Bus {
 
     pub async fn request_boxed(
         &self,
-        req: Box<dyn Message>,
+        mut req: Box<dyn Message>,
         options: SendOptions,
     ) -> Result<Box<dyn Message>, Error<Box<dyn Message>>> {
-        if self.inner.closed.load(Ordering::SeqCst) {
+        if self.refuse_closed() {
             return Err(SendError::Closed(req).into());
         }
 
+        self.before_send(&mut *req)
+            .await
+            .map_err(Error::OtherBoxed)?;
+
         let tt = req.type_tag();
 
         let mut iter = self.select_receivers(tt.clone(), options, None, None, true);
@@ -688,29 +1344,61 @@ impl Bus {
                     .map_msg(|_| unimplemented!())
             })?;
 
+            let mut guard = RequestGuard::new(&rc, mid, self.context());
+            let bus = self.with_context(Some(guard.context.clone()));
+
             rc.send_boxed(
-                self,
+                &bus,
                 mid | 1 << (usize::BITS - 1),
                 req,
                 true,
                 rc.reserve(&tt).await,
             )?;
 
-            rx.await.map_err(|x| x.specify::<Box<dyn Message>>())
+            let resp = rx.await;
+            guard.done = true;
+
+            resp.map_err(|x| x.specify::<Box<dyn Message>>())
         } else {
             Err(Error::NoReceivers)
         }
     }
 
-    pub async fn request_boxed_we<E: StdSyncSendError>(
+    #[inline]
+    pub async fn request_boxed_timeout(
+        &self,
+        req: Box<dyn Message>,
+        options: SendOptions,
+        timeout: Duration,
+    ) -> Result<Box<dyn Message>, Error<Box<dyn Message>>> {
+        self.request_boxed_with_deadline(req, options, tokio::time::Instant::now() + timeout)
+            .await
+    }
+
+    pub async fn request_boxed_with_deadline(
         &self,
         req: Box<dyn Message>,
         options: SendOptions,
+        deadline: tokio::time::Instant,
+    ) -> Result<Box<dyn Message>, Error<Box<dyn Message>>> {
+        tokio::time::timeout_at(deadline, self.request_boxed(req, options))
+            .await
+            .unwrap_or(Err(Error::Timeout))
+    }
+
+    pub async fn request_boxed_we<E: StdSyncSendError>(
+        &self,
+        mut req: Box<dyn Message>,
+        options: SendOptions,
     ) -> Result<Box<dyn Message>, Error<Box<dyn Message>, E>> {
-        if self.inner.closed.load(Ordering::SeqCst) {
+        if self.refuse_closed() {
             return Err(SendError::Closed(req).into());
         }
 
+        self.before_send(&mut *req)
+            .await
+            .map_err(Error::OtherBoxed)?;
+
         let tt = req.type_tag();
         let eid = E::type_tag_();
 
@@ -721,8 +1409,11 @@ impl Bus {
                     .map_msg(|_| unimplemented!())
             })?;
 
+            let mut guard = RequestGuard::new(&rc, mid, self.context());
+            let bus = self.with_context(Some(guard.context.clone()));
+
             rc.send_boxed(
-                self,
+                &bus,
                 mid | 1 << (usize::BITS - 1),
                 req,
                 true,
@@ -730,7 +1421,10 @@ impl Bus {
             )
             .map_err(|x| x.map_err(|_| unimplemented!()))?;
 
-            rx.await.map_err(|x| x.specify::<Box<dyn Message>>())
+            let resp = rx.await;
+            guard.done = true;
+
+            resp.map_err(|x| x.specify::<Box<dyn Message>>())
         } else {
             Err(Error::NoReceivers)
         }
@@ -742,22 +1436,33 @@ impl Bus {
         de: &'b mut dyn erased_serde::Deserializer<'c>,
         _options: SendOptions,
     ) -> Result<(), Error<Box<dyn Message>>> {
-        if self.inner.closed.load(Ordering::SeqCst) {
+        if self.refuse_closed() {
             warn!("closed message bus");
             return Err(Error::NoResponse);
         }
 
         let mid = ID_COUNTER.fetch_add(1, Ordering::Relaxed);
 
-        if let Some(rs) = self
+        let rs = self
             .inner
+            .routes
+            .load()
             .lookup
             .get(&(tt.clone(), None, None))
-            .and_then(|rs| rs.first())
-        {
-            let msg = deserialize_shared_message(tt.clone(), de)?;
+            .and_then(|rs| rs.first().cloned());
+
+        if let Some(rs) = rs {
+            let mut msg = deserialize_shared_message(tt.clone(), de)?.upcast_box();
+            self.before_send(&mut *msg)
+                .await
+                .map_err(Error::OtherBoxed)?;
+
+            let key = match self.dedup(&*msg) {
+                Some(key) => key,
+                None => return Ok(()),
+            };
 
-            Ok(rs.send_boxed(self, mid, msg.upcast_box(), false, rs.reserve(&tt).await)?)
+            key.settle(rs.send_boxed(self, mid, msg, false, rs.reserve(&tt).await))
         } else {
             Err(Error::NoReceivers)
         }
@@ -769,7 +1474,7 @@ impl Bus {
         de: &'b mut dyn erased_serde::Deserializer<'c>,
         options: SendOptions,
     ) -> Result<Box<dyn Message>, Error<Box<dyn Message>>> {
-        if self.inner.closed.load(Ordering::SeqCst) {
+        if self.refuse_closed() {
             warn!("closed message bus");
             return Err(Error::NoResponse);
         }
@@ -777,24 +1482,41 @@ impl Bus {
         let mut iter = self.select_receivers(tt.clone(), options, None, None, true);
         if let Some(rc) = iter.next() {
             let (mid, rx) = rc.add_response_waiter_boxed().unwrap();
-            let msg = deserialize_shared_message(tt.clone(), de)?;
+            let mut guard = RequestGuard::new(&rc, mid, self.context());
+            let bus = self.with_context(Some(guard.context.clone()));
+            let mut msg = deserialize_shared_message(tt.clone(), de)?.upcast_box();
+            self.before_send(&mut *msg)
+                .await
+                .map_err(Error::OtherBoxed)?;
 
             rc.send_boxed(
-                self,
+                &bus,
                 mid | 1 << (usize::BITS - 1),
-                msg.upcast_box(),
+                msg,
                 true,
                 rc.reserve(&tt).await,
             )?;
 
-            rx.await.map_err(|x| x.specify::<Box<dyn Message>>())
+            let resp = rx.await;
+            guard.done = true;
+
+            resp.map_err(|x| x.specify::<Box<dyn Message>>())
         } else {
             Err(Error::NoReceivers)
         }
     }
 
     pub fn stats(&self) -> impl Iterator<Item = Stats> + '_ {
-        self.inner.receivers.iter().map(|x| x.stats())
+        self.inner
+            .routes
+            .load()
+            .receivers
+            .iter()
+            .map(|x| x.stats())
+            .chain(self.inner.timers.iter().map(|x| x.stats()))
+            .chain(self.inner.dedup.values().map(|x| x.stats()))
+            .collect::<Vec<_>>()
+            .into_iter()
     }
 
     #[inline]
@@ -805,17 +1527,120 @@ impl Bus {
         rid: Option<TypeTag>,
         eid: Option<TypeTag>,
         is_req: bool,
-    ) -> impl Iterator<Item = &Receiver> + '_ {
-        self.inner
+    ) -> impl Iterator<Item = Receiver> {
+        let routes = self.inner.routes.load();
+        let receivers = routes
             .lookup
             .get(&(tid.clone(), rid.clone(), eid.clone()))
-            .into_iter()
-            .flatten()
-            .filter(move |r| r.accept(is_req, &tid, rid.as_ref(), eid.as_ref()))
-            .filter(move |r| match options {
-                SendOptions::Except(id) => id != r.id(),
+            .map(|rs| rs.as_slice())
+            .unwrap_or_default();
+
+        let candidates = receivers
+            .iter()
+            .filter(|r| r.accept(is_req, &tid, rid.as_ref(), eid.as_ref()))
+            .filter(|r| !matches!(options, SendOptions::Except(id) if id == r.id()));
+
+        let selected = self.pick_receiver(&tid, options, candidates.clone());
+
+        // grouped receivers get a message only if picked for their group
+        let members = match options {
+            SendOptions::Broadcast | SendOptions::Except(_) => {
+                self.pick_group_members(&tid, None, candidates.clone())
+            }
+            SendOptions::Group(group) => {
+                self.pick_group_members(&tid, Some(group), candidates.clone())
+            }
+            _ => SmallVec::new(),
+        };
+
+        candidates
+            .filter(|r| match options {
+                SendOptions::Broadcast | SendOptions::Except(_) => {
+                    r.group().is_none() || members.contains(&r.id())
+                }
                 SendOptions::Direct(id) => id == r.id(),
-                _ => true,
+                SendOptions::Balanced | SendOptions::Random | SendOptions::RoundRobin => {
+                    selected == Some(r.id())
+                }
+                SendOptions::Group(_) => members.contains(&r.id()),
+            })
+            .cloned()
+            .collect::<SmallVec<[Receiver; 4]>>()
+            .into_iter()
+    }
+
+    fn pick_receiver<'a>(
+        &self,
+        tid: &TypeTag,
+        options: SendOptions,
+        mut candidates: impl Iterator<Item = &'a Receiver> + Clone,
+    ) -> Option<u64> {
+        let picked = match options {
+            SendOptions::Balanced => candidates
+                .fold(None, |best: Option<(&Receiver, i64)>, r| {
+                    let free = r.free_capacity(tid);
+
+                    match best {
+                        Some((_, best_free)) if best_free >= free => best,
+                        _ => Some((r, free)),
+                    }
+                })
+                .map(|(r, _)| r),
+
+            SendOptions::Random => {
+                let count = candidates.clone().count() as u64;
+                if count == 0 {
+                    return None;
+                }
+
+                candidates.nth(self.inner.rng.next_bounded(count) as _)
+            }
+
+            SendOptions::RoundRobin => {
+                let count = candidates.clone().count() as u64;
+                if count == 0 {
+                    return None;
+                }
+
+                let idx = if let Some(counter) = self.inner.round_robin.get(tid) {
+                    counter.fetch_add(1, Ordering::Relaxed)
+                } else {
+                    self.inner
+                        .round_robin
+                        .entry(tid.clone())
+                        .or_default()
+                        .fetch_add(1, Ordering::Relaxed)
+                };
+
+                candidates.nth((idx % count) as _)
+            }
+
+            _ => None,
+        };
+
+        picked.map(Receiver::id)
+    }
+
+    // one member of each consumer group among `candidates` (or of `only`)
+    fn pick_group_members<'a>(
+        &self,
+        tid: &TypeTag,
+        only: Option<&str>,
+        candidates: impl Iterator<Item = &'a Receiver> + Clone,
+    ) -> SmallVec<[u64; 4]> {
+        let mut groups = SmallVec::<[&str; 4]>::new();
+        for group in candidates.clone().filter_map(Receiver::group) {
+            if only.is_none_or(|only| only == group) && !groups.contains(&group) {
+                groups.push(group);
+            }
+        }
+
+        groups
+            .into_iter()
+            .filter_map(|group| {
+                let members = candidates.clone().filter(|r| r.group() == Some(group));
+                self.pick_receiver(tid, SendOptions::Balanced, members)
             })
+            .collect()
     }
 }
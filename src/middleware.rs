@@ -0,0 +1,127 @@
+use async_trait::async_trait;
+use futures::FutureExt;
+
+use crate::{
+    envelop::TypeTag,
+    error::{Error, GenericError, StdSyncSendError},
+    receivers::OverflowPolicy,
+    Bus, Message,
+};
+
+// Interceptor registered with `BusBuilder::middleware`. Hooks run in registration
+// order; a hook may inspect or mutate the message in place, delay it by awaiting,
+// or reject it by returning an error. `try_send`, `force_send` and `try_send_one`
+// can not wait: an `on_send` that does not complete right away fails them with
+// `Error::Other`.
+#[async_trait]
+pub trait Middleware: Send + Sync + 'static {
+    // before the message is routed to receivers
+    async fn on_send(
+        &self,
+        _tt: &TypeTag,
+        _msg: &mut dyn Message,
+        _bus: &Bus,
+    ) -> Result<(), Box<dyn StdSyncSendError>> {
+        Ok(())
+    }
+
+    // in the receiver poller, right before the message is handed to the handler
+    async fn before_handle(
+        &self,
+        _receiver_id: u64,
+        _tt: &TypeTag,
+        _msg: &mut dyn Message,
+        _bus: &Bus,
+    ) -> Result<(), Box<dyn StdSyncSendError>> {
+        Ok(())
+    }
+
+    // after the handler has finished with the message
+    async fn after_handle(
+        &self,
+        _receiver_id: u64,
+        _tt: &TypeTag,
+        _err: Option<&dyn StdSyncSendError>,
+        _bus: &Bus,
+    ) {
+    }
+
+    // a message was dropped by a full receiver's overflow policy
+    fn on_drop(&self, _receiver_id: u64, _tt: &TypeTag, _policy: OverflowPolicy, _bus: &Bus) {}
+}
+
+impl Bus {
+    pub(crate) async fn before_send(
+        &self,
+        msg: &mut dyn Message,
+    ) -> Result<(), Box<dyn StdSyncSendError>> {
+        if self.inner.middlewares.is_empty() {
+            return Ok(());
+        }
+
+        let tt = msg.type_tag();
+        for m in self.inner.middlewares.iter() {
+            m.on_send(&tt, msg, self).await?;
+        }
+
+        Ok(())
+    }
+
+    // used by the non-async send paths, which can not wait: a pending middleware
+    // fails the send with its own error rather than passing for a full queue
+    pub(crate) fn try_before_send<M: Message>(&self, mut msg: M) -> Result<M, Error<M>> {
+        if self.inner.middlewares.is_empty() {
+            return Ok(msg);
+        }
+
+        let res = self.before_send(&mut msg).now_or_never();
+        match res {
+            Some(Ok(())) => Ok(msg),
+            Some(Err(err)) => Err(Error::OtherBoxed(err)),
+            None => Err(Error::Other(GenericError::from_err(
+                msg.type_tag(),
+                "middleware pending",
+            ))),
+        }
+    }
+
+    pub(crate) async fn before_handle(
+        &self,
+        receiver_id: u64,
+        msg: &mut dyn Message,
+    ) -> Result<(), Box<dyn StdSyncSendError>> {
+        if self.inner.middlewares.is_empty() {
+            return Ok(());
+        }
+
+        let tt = msg.type_tag();
+        for m in self.inner.middlewares.iter() {
+            m.before_handle(receiver_id, &tt, msg, self).await?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn on_drop(&self, receiver_id: u64, tt: &TypeTag, policy: OverflowPolicy) {
+        for m in self.inner.middlewares.iter() {
+            m.on_drop(receiver_id, tt, policy, self);
+        }
+    }
+
+    pub(crate) async fn after_handle<M: Message, E: StdSyncSendError>(
+        &self,
+        receiver_id: u64,
+        err: Option<&E>,
+    ) {
+        if self.inner.middlewares.is_empty() {
+            return;
+        }
+
+        let tt = M::type_tag_();
+        let err = err.map(|e| e as &dyn StdSyncSendError);
+
+        for m in self.inner.middlewares.iter() {
+            m.after_handle(receiver_id, &tt, err, self).await;
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use core::marker::PhantomData;
+use std::sync::Arc;
+
+use tokio::sync::Mutex;
+
+use crate::{
+    builder::{new_receiver, BusBuilder, ReceiverSubscriberBuilder},
+    error::StdSyncSendError,
+    receiver::Receiver,
+    receivers, AsyncBatchHandler, AsyncBatchSynchronizedHandler, AsyncHandler,
+    AsyncSynchronizedHandler, BatchHandler, BatchSynchronizedHandler, Bus, Handler, Message,
+    SynchronizedHandler, Untyped,
+};
+
+// Chain of handlers: every `Ok` response of a stage is sent straight to the
+// receiver of the next stage, not broadcast to the other subscribers of its
+// type. Responses of the last stage are dropped, unless its config sets
+// `resend_unused_resp`. Responses are forwarded with the context of the message
+// they answer, and an ordered shutdown drains the stages upstream first.
+#[must_use]
+pub struct Pipeline<I, O> {
+    builder: BusBuilder,
+    stages: Vec<Receiver>,
+    _m: PhantomData<fn(I) -> O>,
+}
+
+impl BusBuilder {
+    pub fn pipeline<I: Message>(self) -> Pipeline<I, I> {
+        Pipeline {
+            builder: self,
+            stages: Vec::new(),
+            _m: Default::default(),
+        }
+    }
+}
+
+impl<I: Message, O: Message> Pipeline<I, O> {
+    pub fn stage_with<T, S, R, E>(self, handler: T, queue: u64, cfg: S::Config) -> Pipeline<I, R>
+    where
+        T: Send + Sync + 'static,
+        R: Message,
+        E: StdSyncSendError,
+        S: ReceiverSubscriberBuilder<T, O, R, E> + 'static,
+    {
+        self.push_stage::<T, S, R, E>(Arc::new(handler) as Untyped, queue, cfg)
+    }
+
+    // like `stage_with`, for the handlers that need `&mut self`
+    pub fn stage_unsync_with<T, S, R, E>(
+        self,
+        handler: T,
+        queue: u64,
+        cfg: S::Config,
+    ) -> Pipeline<I, R>
+    where
+        T: Send + 'static,
+        R: Message,
+        E: StdSyncSendError,
+        S: ReceiverSubscriberBuilder<T, O, R, E> + 'static,
+    {
+        self.push_stage::<T, S, R, E>(Arc::new(Mutex::new(handler)) as Untyped, queue, cfg)
+    }
+
+    fn push_stage<T, S, R, E>(
+        mut self,
+        handler: Untyped,
+        queue: u64,
+        cfg: S::Config,
+    ) -> Pipeline<I, R>
+    where
+        T: 'static,
+        R: Message,
+        E: StdSyncSendError,
+        S: ReceiverSubscriberBuilder<T, O, R, E> + 'static,
+    {
+        let (receiver, poller) = new_receiver::<T, O, R, E, S>(queue, cfg);
+        if let Some(prev) = self.stages.last() {
+            prev.forward_responses(receiver.id());
+        }
+
+        let module = &mut self.builder.inner;
+        module.pollings.push(poller(handler));
+        module.pollings.push(receiver.start_polling());
+        module.receivers.insert(receiver.clone());
+        self.stages.push(receiver);
+
+        Pipeline {
+            builder: self.builder,
+            stages: self.stages,
+            _m: Default::default(),
+        }
+    }
+
+    #[inline]
+    pub fn stage<T>(
+        self,
+        handler: T,
+        queue: u64,
+        cfg: receivers::BufferUnorderedConfig,
+    ) -> Pipeline<I, T::Response>
+    where
+        T: AsyncHandler<O> + Send + Sync + 'static,
+        T::Response: Message,
+        T::Error: StdSyncSendError,
+    {
+        self.stage_with::<T, receivers::BufferUnorderedAsync<O, T::Response, T::Error>, T::Response, T::Error>(handler, queue, cfg)
+    }
+
+    #[inline]
+    pub fn stage_sync<T>(
+        self,
+        handler: T,
+        queue: u64,
+        cfg: receivers::BufferUnorderedConfig,
+    ) -> Pipeline<I, T::Response>
+    where
+        T: Handler<O> + Send + Sync + 'static,
+        T::Response: Message,
+    {
+        self.stage_with::<T, receivers::BufferUnorderedSync<O, T::Response, T::Error>, T::Response, T::Error>(handler, queue, cfg)
+    }
+
+    #[inline]
+    pub fn stage_batch<T>(
+        self,
+        handler: T,
+        queue: u64,
+        cfg: receivers::BufferUnorderedBatchedConfig,
+    ) -> Pipeline<I, T::Response>
+    where
+        T: AsyncBatchHandler<O> + Send + Sync + 'static,
+        T::Response: Message,
+    {
+        self.stage_with::<T, receivers::BufferUnorderedBatchedAsync<O, T::Response, T::Error>, T::Response, T::Error>(handler, queue, cfg)
+    }
+
+    #[inline]
+    pub fn stage_batch_sync<T>(
+        self,
+        handler: T,
+        queue: u64,
+        cfg: receivers::BufferUnorderedBatchedConfig,
+    ) -> Pipeline<I, T::Response>
+    where
+        T: BatchHandler<O> + Send + Sync + 'static,
+        T::Response: Message,
+    {
+        self.stage_with::<T, receivers::BufferUnorderedBatchedSync<O, T::Response, T::Error>, T::Response, T::Error>(handler, queue, cfg)
+    }
+
+    #[inline]
+    pub fn stage_synchronized<T>(
+        self,
+        handler: T,
+        queue: u64,
+        cfg: receivers::SynchronizedConfig,
+    ) -> Pipeline<I, T::Response>
+    where
+        T: AsyncSynchronizedHandler<O> + Send + 'static,
+        T::Response: Message,
+        T::Error: StdSyncSendError,
+    {
+        self.stage_unsync_with::<T, receivers::SynchronizedAsync<O, T::Response, T::Error>, T::Response, T::Error>(handler, queue, cfg)
+    }
+
+    #[inline]
+    pub fn stage_synchronized_sync<T>(
+        self,
+        handler: T,
+        queue: u64,
+        cfg: receivers::SynchronizedConfig,
+    ) -> Pipeline<I, T::Response>
+    where
+        T: SynchronizedHandler<O> + Send + 'static,
+        T::Response: Message,
+    {
+        self.stage_unsync_with::<T, receivers::SynchronizedSync<O, T::Response, T::Error>, T::Response, T::Error>(handler, queue, cfg)
+    }
+
+    #[inline]
+    pub fn stage_batch_synchronized<T>(
+        self,
+        handler: T,
+        queue: u64,
+        cfg: receivers::SynchronizedBatchedConfig,
+    ) -> Pipeline<I, T::Response>
+    where
+        T: AsyncBatchSynchronizedHandler<O> + Send + 'static,
+        T::Response: Message,
+    {
+        self.stage_unsync_with::<T, receivers::SynchronizedBatchedAsync<O, T::Response, T::Error>, T::Response, T::Error>(handler, queue, cfg)
+    }
+
+    #[inline]
+    pub fn stage_batch_synchronized_sync<T>(
+        self,
+        handler: T,
+        queue: u64,
+        cfg: receivers::SynchronizedBatchedConfig,
+    ) -> Pipeline<I, T::Response>
+    where
+        T: BatchSynchronizedHandler<O> + Send + 'static,
+        T::Response: Message,
+    {
+        self.stage_unsync_with::<T, receivers::SynchronizedBatchedSync<O, T::Response, T::Error>, T::Response, T::Error>(handler, queue, cfg)
+    }
+
+    // pipelines sharing their input type are kept apart, each flushed and
+    // closed along its own stages
+    pub fn done(mut self) -> BusBuilder {
+        self.builder
+            .pipelines
+            .entry(I::type_tag_())
+            .or_default()
+            .push(self.stages);
+
+        self.builder
+    }
+}
+
+impl Bus {
+    // flushes the stages of the pipelines starting with `M`, first to last, so
+    // the responses of a stage are queued on the next one before it is flushed
+    pub async fn flush_pipeline<M: Message>(&self) {
+        for stages in self.pipeline_stages::<M>() {
+            for r in stages {
+                r.flush(self).await;
+            }
+        }
+    }
+
+    pub async fn idle_pipeline<M: Message>(&self) {
+        for stages in self.pipeline_stages::<M>() {
+            for r in stages {
+                r.flush(self).await;
+                r.idle().await;
+            }
+        }
+    }
+
+    fn pipeline_stages<M: Message>(&self) -> impl Iterator<Item = &Vec<Receiver>> {
+        self.inner
+            .pipelines
+            .get(&M::type_tag_())
+            .into_iter()
+            .flatten()
+    }
+}
@@ -1,11 +1,15 @@
+use crate::context::Context;
+use crate::receivers::{
+    CircuitBreaker, CircuitBreakerConfig, EvictionQueue, OverflowPolicy, RateLimit, TokenBucket,
+};
 use crate::relay::RelayWrapper;
 use crate::stats::Stats;
 use crate::Untyped;
 use crate::{
     envelop::{IntoBoxedMessage, TypeTag},
-    error::{GenericError, StdSyncSendError},
+    error::{GenericError, SendError, StdSyncSendError},
     trait_object::TraitObject,
-    Bus, Error, Message, Relay,
+    Bus, Error, Message, Relay, SendOptions,
 };
 use core::{
     any::{Any, TypeId},
@@ -13,8 +17,10 @@ use core::{
     marker::PhantomData,
     mem,
     pin::Pin,
-    sync::atomic::{AtomicBool, AtomicI64, Ordering},
+    sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering},
+    time::Duration,
 };
+use dashmap::DashMap;
 use futures::{pin_mut, Stream};
 use futures::{Future, FutureExt, StreamExt};
 use std::hash::{Hash, Hasher};
@@ -42,6 +48,16 @@ pub trait SendUntypedReceiver: Send + Sync {
     ) -> Result<(), Error<Box<dyn Message>>> {
         unimplemented!()
     }
+
+    // producers are closed before the other receivers on shutdown
+    fn is_producer(&self) -> bool {
+        false
+    }
+
+    // largest and current batch size of a batching receiver
+    fn batch_stats(&self) -> Option<(usize, usize)> {
+        None
+    }
 }
 
 pub trait SendTypedReceiver<M: Message>: Sync {
@@ -112,6 +128,7 @@ pub trait ReceiverTrait: TypeTagAccept + Send + Sync {
         &self,
         listener: oneshot::Sender<Result<Box<dyn Message>, Error>>,
     ) -> Result<u64, Error>;
+    fn remove_response_listener(&self, mid: u64) -> bool;
 
     fn stats(&self) -> Stats;
 
@@ -127,10 +144,59 @@ pub trait ReceiverTrait: TypeTagAccept + Send + Sync {
     fn is_idling(&self) -> bool;
     fn need_flush(&self) -> bool;
     fn set_need_flush(&self);
+    fn clear_need_flush(&self);
+
+    fn circuit_allow(&self) -> bool {
+        true
+    }
+
+    fn circuit_enter(&self) {}
+
+    fn rate_limit_delay(&self) -> Option<Duration> {
+        None
+    }
+
+    fn is_producer(&self) -> bool {
+        false
+    }
+
+    fn overflow_policy(&self) -> OverflowPolicy {
+        OverflowPolicy::Block
+    }
+
+    fn evict_oldest(&self) -> bool {
+        false
+    }
+
+    fn record_drop(&self) {}
+
+    // messages skipped by the receiver as cancelled
+    fn cancelled_count(&self) -> u64 {
+        0
+    }
+
+    fn processing_count(&self) -> i64 {
+        0
+    }
+
+    // sends the unused responses to the given receiver only
+    fn forward_responses(&self, _receiver_id: u64) {}
+
+    // the bus a message is queued with, carrying the message's own context
+    fn message_bus(&self, bus: &Bus) -> Bus {
+        bus.clone()
+    }
+
+    // keeps the context of a queued message for its response to be resent with
+    fn keep_context(&self, _mid: u64, _bus: &Bus) {}
+
+    // drops the context kept for a message that could not be queued after all
+    fn forget_context(&self, _mid: u64) {}
 
     fn try_reserve(&self, tt: &TypeTag) -> Option<Permit>;
     fn reserve_notify(&self, tt: &TypeTag) -> Arc<Notify>;
     fn increment_processing(&self, tt: &TypeTag);
+    fn free_capacity(&self, tt: &TypeTag) -> i64;
 
     fn start_polling(self: Arc<Self>) -> BusPollerCallback;
 }
@@ -212,7 +278,7 @@ where
         Box::new(move |bus| {
             Box::pin(async move {
                 let this = self.clone();
-                let events = this.inner.event_stream(bus);
+                let events = this.inner.event_stream(bus.clone());
                 pin_mut!(events);
 
                 loop {
@@ -240,10 +306,7 @@ where
                             self.context.closed.notify_waiters();
                             break;
                         }
-                        Event::Flushed => {
-                            self.context.need_flush.store(false, Ordering::SeqCst);
-                            self.context.flushed.notify_one();
-                        }
+                        Event::Flushed => self.context.flushed.notify_one(),
                         Event::Synchronized(_res) => self.context.synchronized.notify_waiters(),
                         Event::Response(mid, resp) => {
                             let prev_value = self.context.processing.fetch_sub(1, Ordering::SeqCst);
@@ -254,10 +317,43 @@ where
 
                             self.context.response.notify_one();
 
+                            match &resp {
+                                Ok(_) => self.context.breaker.record(true),
+                                Err(Error::Other(_)) => self.context.breaker.record(false),
+                                Err(Error::Cancelled) if !self.context.evictable.skipped() => {
+                                    self.context.cancelled.fetch_add(1, Ordering::Relaxed);
+                                }
+                                Err(Error::Expired) => {
+                                    self.context.expired.fetch_add(1, Ordering::Relaxed);
+                                }
+                                Err(_) => (),
+                            }
+
+                            // the response is resent with the context of its message:
+                            // headers, scope and deadline carry over
+                            let ctx = self
+                                .context
+                                .resend_contexts
+                                .remove(&mid)
+                                .map(|(_, ctx)| ctx);
+
                             match self.response(mid, resp) {
-                                Ok(Some(_resp)) => {
-                                    if self.context.resend_unused_resp {
-                                        // TODO
+                                Ok(Some(resp)) => {
+                                    if self.context.resend_unused_resp.load(Ordering::Relaxed) {
+                                        let options =
+                                            match self.context.resend_to.load(Ordering::Relaxed) {
+                                                0 => SendOptions::Broadcast,
+                                                id => SendOptions::Direct(id),
+                                            };
+
+                                        let res = bus
+                                            .with_context(ctx)
+                                            .send_boxed(resp.into_boxed(), options)
+                                            .await;
+
+                                        if let Err(err) = res {
+                                            warn!("Response resend error: {}", err);
+                                        }
                                     }
                                 }
 
@@ -296,16 +392,17 @@ where
     fn response(&self, mid: u64, resp: Result<R, Error<(), E>>) -> Result<Option<R>, Error> {
         Ok(if let Some(waiter) = self.waiters.take(mid as _) {
             match waiter {
-                Waiter::WithErrorType(sender) => sender.send(resp).unwrap(),
+                Waiter::WithErrorType(sender) => {
+                    let _ = sender.send(resp);
+                }
                 Waiter::WithoutErrorType(sender) => {
-                    sender.send(resp.map_err(|e| e.into_dyn())).unwrap()
+                    let _ = sender.send(resp.map_err(|e| e.into_dyn()));
+                }
+                Waiter::Boxed(sender) => {
+                    let _ = sender.send(resp.map_err(|e| e.into_dyn()).map(|x| x.into_boxed()));
                 }
-                Waiter::Boxed(sender) => sender
-                    .send(resp.map_err(|e| e.into_dyn()).map(|x| x.into_boxed()))
-                    .unwrap(),
-
                 Waiter::BoxedWithError(sender) => {
-                    sender.send(resp.map(|x| x.into_boxed())).unwrap()
+                    let _ = sender.send(resp.map(|x| x.into_boxed()));
                 }
             }
             None
@@ -435,7 +532,13 @@ where
             .ok_or(Error::AddListenerError)? as _)
     }
 
+    fn remove_response_listener(&self, mid: u64) -> bool {
+        self.waiters.remove(mid as _)
+    }
+
     fn stats(&self) -> Stats {
+        let batch = SendUntypedReceiver::batch_stats(&self.inner);
+
         Stats {
             msg_type_tag: M::type_tag_(),
             resp_type_tag: Some(R::type_tag_()),
@@ -445,6 +548,18 @@ where
             queue_capacity: self.context.limit as _,
             queue_size: self.context.processing.load(Ordering::Relaxed) as _,
 
+            has_circuit_breaker: self.context.breaker.is_enabled(),
+            circuit_state: self.context.breaker.state(),
+
+            overflow_policy: self.context.overflow,
+            overflow_dropped: self.context.dropped.load(Ordering::Relaxed) as _,
+            cancelled_count: self.context.cancelled.load(Ordering::Relaxed) as _,
+            expired_count: self.context.expired.load(Ordering::Relaxed) as _,
+
+            has_batch: batch.is_some(),
+            batch_capacity: batch.map_or(0, |(capacity, _)| capacity as _),
+            batch_size: batch.map_or(0, |(_, size)| size as _),
+
             ..Default::default()
         }
     }
@@ -493,7 +608,81 @@ where
         self.context.need_flush.store(true, Ordering::SeqCst);
     }
 
+    fn clear_need_flush(&self) {
+        self.context.need_flush.store(false, Ordering::SeqCst);
+    }
+
+    fn circuit_allow(&self) -> bool {
+        self.context.breaker.allow()
+    }
+
+    fn circuit_enter(&self) {
+        self.context.breaker.enter()
+    }
+
+    fn rate_limit_delay(&self) -> Option<Duration> {
+        self.context.rate_limit.delay()
+    }
+
+    fn is_producer(&self) -> bool {
+        SendUntypedReceiver::is_producer(&self.inner)
+    }
+
+    fn overflow_policy(&self) -> OverflowPolicy {
+        self.context.overflow
+    }
+
+    fn evict_oldest(&self) -> bool {
+        self.context.evictable.evict()
+    }
+
+    fn record_drop(&self) {
+        self.context.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn cancelled_count(&self) -> u64 {
+        self.context.cancelled.load(Ordering::Relaxed)
+    }
+
+    fn forward_responses(&self, receiver_id: u64) {
+        self.context.resend_to.store(receiver_id, Ordering::Relaxed);
+        self.context
+            .resend_unused_resp
+            .store(true, Ordering::Relaxed);
+    }
+
+    fn message_bus(&self, bus: &Bus) -> Bus {
+        let deadline = bus.message_deadline(&M::type_tag_());
+
+        if self.context.overflow == OverflowPolicy::DropOldest {
+            let ctx = Context::for_message(bus.context(), true, deadline);
+            if let Some(ctx) = &ctx {
+                self.context.evictable.push(ctx);
+            }
+
+            bus.with_context(ctx)
+        } else {
+            bus.with_context(bus.message_context(deadline))
+        }
+    }
+
+    fn keep_context(&self, mid: u64, bus: &Bus) {
+        if self.context.resend_unused_resp.load(Ordering::Relaxed) {
+            if let Some(ctx) = bus.context() {
+                self.context.resend_contexts.insert(mid, ctx);
+            }
+        }
+    }
+
+    fn forget_context(&self, mid: u64) {
+        self.context.resend_contexts.remove(&mid);
+    }
+
     fn try_reserve(&self, _: &TypeTag) -> Option<Permit> {
+        if !self.context.rate_limit.try_acquire() {
+            return None;
+        }
+
         loop {
             let count = self.context.processing.load(Ordering::Relaxed);
 
@@ -507,13 +696,21 @@ where
                 if res.is_ok() {
                     break Some(Permit {
                         fuse: false,
+                        overflow: false,
                         inner: self.context.clone(),
                     });
                 }
 
                 // continue
             } else {
-                break None;
+                self.context.rate_limit.release();
+
+                // a non-blocking policy deals with the message on send
+                break (!self.context.overflow.is_blocking()).then(|| Permit {
+                    fuse: true,
+                    overflow: true,
+                    inner: self.context.clone(),
+                });
             }
         }
     }
@@ -526,6 +723,14 @@ where
         self.context.processing.fetch_add(1, Ordering::SeqCst);
     }
 
+    fn processing_count(&self) -> i64 {
+        self.context.processing.load(Ordering::Relaxed)
+    }
+
+    fn free_capacity(&self, _tt: &TypeTag) -> i64 {
+        self.context.limit as i64 - self.context.processing.load(Ordering::Relaxed)
+    }
+
     fn start_polling(self: Arc<Self>) -> BusPollerCallback {
         self.start_polling_events()
     }
@@ -533,6 +738,8 @@ where
 
 pub struct Permit {
     pub(crate) fuse: bool,
+    // issued over capacity, for the receiver's overflow policy to handle
+    pub(crate) overflow: bool,
     pub(crate) inner: Arc<dyn PermitDrop + Send + Sync>,
 }
 
@@ -714,12 +921,24 @@ struct ReceiverContext {
     idle: Notify,
     response: Arc<Notify>,
     init_sent: AtomicBool,
-    resend_unused_resp: bool,
+    resend_unused_resp: AtomicBool,
+    // receiver the unused responses are sent to, 0 to broadcast them
+    resend_to: AtomicU64,
+    // contexts of the queued messages whose responses are resent, by mid
+    resend_contexts: DashMap<u64, Arc<Context>>,
+    breaker: CircuitBreaker,
+    rate_limit: TokenBucket,
+    overflow: OverflowPolicy,
+    evictable: EvictionQueue,
+    dropped: AtomicU64,
+    cancelled: AtomicU64,
+    expired: AtomicU64,
 }
 
 impl PermitDrop for ReceiverContext {
     fn permit_drop(&self) {
         self.processing.fetch_sub(1, Ordering::SeqCst);
+        self.rate_limit.release();
     }
 }
 
@@ -733,6 +952,14 @@ enum Waiter<R: Message, E: StdSyncSendError> {
 #[derive(Clone)]
 pub struct Receiver {
     inner: Arc<dyn ReceiverTrait>,
+    // consumer group the receiver is a member of
+    group: Option<&'static str>,
+}
+
+enum Overflow {
+    Queue,
+    Reject,
+    Drop,
 }
 
 impl Hash for Receiver {
@@ -758,7 +985,15 @@ impl core::cmp::Eq for Receiver {}
 
 impl Receiver {
     #[inline]
-    pub(crate) fn new<M, R, E, S>(id: u64, limit: u64, resend: bool, inner: S) -> Self
+    pub(crate) fn new<M, R, E, S>(
+        id: u64,
+        limit: u64,
+        resend: bool,
+        breaker: CircuitBreakerConfig,
+        rate_limit: RateLimit,
+        overflow: OverflowPolicy,
+        inner: S,
+    ) -> Self
     where
         M: Message,
         R: Message,
@@ -766,6 +1001,7 @@ impl Receiver {
         S: SendUntypedReceiver + SendTypedReceiver<M> + ReciveTypedReceiver<R, E> + 'static,
     {
         Self {
+            group: None,
             inner: Arc::new(ReceiverWrapper {
                 id,
                 inner,
@@ -782,7 +1018,16 @@ impl Receiver {
                     ready: Notify::new(),
                     idle: Notify::new(),
                     response: Arc::new(Notify::new()),
-                    resend_unused_resp: resend,
+                    resend_unused_resp: AtomicBool::new(resend),
+                    resend_to: AtomicU64::new(0),
+                    resend_contexts: DashMap::new(),
+                    breaker: CircuitBreaker::new(breaker),
+                    rate_limit: TokenBucket::new(rate_limit),
+                    overflow,
+                    evictable: Default::default(),
+                    dropped: AtomicU64::new(0),
+                    cancelled: AtomicU64::new(0),
+                    expired: AtomicU64::new(0),
                 }),
                 _m: Default::default(),
             }),
@@ -796,9 +1041,20 @@ impl Receiver {
     {
         Self {
             inner: Arc::new(RelayWrapper::new(id, inner)),
+            group: None,
         }
     }
 
+    #[inline]
+    pub(crate) fn in_group(self, group: Option<&'static str>) -> Self {
+        Self { group, ..self }
+    }
+
+    #[inline]
+    pub fn group(&self) -> Option<&'static str> {
+        self.group
+    }
+
     #[inline]
     pub fn id(&self) -> u64 {
         self.inner.id()
@@ -834,13 +1090,43 @@ impl Receiver {
         self.inner.need_flush()
     }
 
+    #[inline]
+    pub fn is_producer(&self) -> bool {
+        self.inner.is_producer()
+    }
+
+    #[inline]
+    pub(crate) fn forward_responses(&self, receiver_id: u64) {
+        self.inner.forward_responses(receiver_id)
+    }
+
+    #[inline]
+    pub(crate) fn cancelled_count(&self) -> u64 {
+        self.inner.cancelled_count()
+    }
+
+    #[inline]
+    pub(crate) fn queue_size(&self) -> u64 {
+        self.inner.processing_count().max(0) as _
+    }
+
     #[inline]
     pub async fn reserve(&self, tt: &TypeTag) -> Permit {
         loop {
             if let Some(p) = self.inner.try_reserve(tt) {
                 return p;
+            }
+
+            let notify = self.inner.reserve_notify(tt);
+            let notified = notify.notified();
+
+            // a rate limited receiver frees up on a timer, not on a response
+            if let Some(delay) = self.inner.rate_limit_delay() {
+                let sleep = tokio::time::sleep(delay);
+                pin_mut!(notified, sleep);
+                futures::future::select(notified, sleep).await;
             } else {
-                self.inner.reserve_notify(tt).notified().await
+                notified.await
             }
         }
     }
@@ -850,6 +1136,11 @@ impl Receiver {
         self.inner.try_reserve(tt)
     }
 
+    #[inline]
+    pub fn free_capacity(&self, tt: &TypeTag) -> i64 {
+        self.inner.free_capacity(tt)
+    }
+
     #[inline]
     pub fn send<M: Message>(
         &self,
@@ -859,6 +1150,23 @@ impl Receiver {
         req: bool,
         mut permit: Permit,
     ) -> Result<(), Error<M>> {
+        if !self.inner.circuit_allow() {
+            return Err(Error::CircuitOpen);
+        }
+
+        if permit.overflow {
+            match self.overflow(bus, &M::type_tag_(), req) {
+                Overflow::Queue => self.inner.increment_processing(&M::type_tag_()),
+                Overflow::Reject => return Err(SendError::Full(msg).into()),
+                Overflow::Drop => return Ok(()),
+            }
+        }
+
+        self.inner.circuit_enter();
+        let bus = &self.inner.message_bus(bus);
+        if !req {
+            self.inner.keep_context(mid, bus);
+        }
         let res = if let Some(any_receiver) = self.inner.typed() {
             any_receiver
                 .cast_send_typed::<M>()
@@ -872,6 +1180,10 @@ impl Receiver {
                 .map(|_| ())
         };
 
+        if res.is_err() && !req {
+            self.inner.forget_context(mid);
+        }
+
         permit.fuse = true;
         self.inner.set_need_flush();
 
@@ -886,8 +1198,26 @@ impl Receiver {
         msg: M,
         req: bool,
     ) -> Result<(), Error<M>> {
-        self.inner.increment_processing(&M::type_tag_());
+        if !self.inner.circuit_allow() {
+            return Err(Error::CircuitOpen);
+        }
 
+        let tt = M::type_tag_();
+        if !self.inner.overflow_policy().is_blocking() && self.inner.free_capacity(&tt) <= 0 {
+            match self.overflow(bus, &tt, req) {
+                Overflow::Queue => (),
+                Overflow::Reject => return Err(SendError::Full(msg).into()),
+                Overflow::Drop => return Ok(()),
+            }
+        }
+
+        self.inner.increment_processing(&tt);
+
+        self.inner.circuit_enter();
+        let bus = &self.inner.message_bus(bus);
+        if !req {
+            self.inner.keep_context(mid, bus);
+        }
         let res = if let Some(any_receiver) = self.inner.typed() {
             any_receiver
                 .cast_send_typed::<M>()
@@ -900,6 +1230,11 @@ impl Receiver {
                 .map_err(|err| err.map_msg(|b| *b.as_any_boxed().downcast::<M>().unwrap()))
                 .map(|_| ())
         };
+
+        if res.is_err() && !req {
+            self.inner.forget_context(mid);
+        }
+
         self.inner.set_need_flush();
 
         res
@@ -914,12 +1249,61 @@ impl Receiver {
         req: bool,
         mut permit: Permit,
     ) -> Result<(), Error<Box<dyn Message>>> {
+        if !self.inner.circuit_allow() {
+            return Err(Error::CircuitOpen);
+        }
+
+        if permit.overflow {
+            let tt = msg.type_tag();
+            match self.overflow(bus, &tt, req) {
+                Overflow::Queue => self.inner.increment_processing(&tt),
+                Overflow::Reject => return Err(SendError::Full(msg).into()),
+                Overflow::Drop => return Ok(()),
+            }
+        }
+
+        self.inner.circuit_enter();
+        let bus = &self.inner.message_bus(bus);
+        if !req {
+            self.inner.keep_context(mid, bus);
+        }
         let res = self.inner.send_boxed(mid, msg, req, bus);
+        if res.is_err() && !req {
+            self.inner.forget_context(mid);
+        }
+
         permit.fuse = true;
         self.inner.set_need_flush();
         res
     }
 
+    // a message sent to a full queue under a non-blocking overflow policy;
+    // requests are rejected rather than dropped, so no requester is left waiting
+    fn overflow(&self, bus: &Bus, tt: &TypeTag, req: bool) -> Overflow {
+        let policy = self.inner.overflow_policy();
+
+        match policy {
+            OverflowPolicy::Block => return Overflow::Queue,
+            OverflowPolicy::Reject => return Overflow::Reject,
+            OverflowPolicy::DropOldest if self.inner.evict_oldest() => {
+                self.inner.record_drop();
+                bus.on_drop(self.id(), tt, policy);
+                return Overflow::Queue;
+            }
+            _ if req => return Overflow::Reject,
+            _ => (),
+        }
+
+        self.inner.record_drop();
+        bus.on_drop(self.id(), tt, policy);
+        Overflow::Drop
+    }
+
+    #[inline]
+    pub(crate) fn remove_response_waiter(&self, mid: u64) -> bool {
+        self.inner.remove_response_listener(mid)
+    }
+
     #[inline]
     pub fn start_polling(&self) -> BusPollerCallback {
         self.inner.clone().start_polling()
@@ -1079,6 +1463,10 @@ impl Receiver {
     pub async fn flush(&self, bus: &Bus) {
         let notify = self.inner.flush_notify().notified();
 
+        // cleared before the flush is queued: a message sent meanwhile marks
+        // the receiver again instead of being lost by the flush completing
+        self.inner.clear_need_flush();
+
         if self.inner.send_action(bus, Action::Flush).is_ok() {
             notify.await;
         } else {
@@ -0,0 +1,153 @@
+use core::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use serde_derive::{Deserialize, Serialize};
+
+// What an adaptive batched receiver tunes its batch size for.
+#[derive(Copy, Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub enum AdaptiveTarget {
+    // the batch size stays at `batch_size`
+    #[default]
+    Disabled,
+    // time the handler takes for one batch
+    Latency(Duration),
+    // as many messages handled per second as possible
+    Throughput,
+}
+
+// Grows or shrinks the batch size of a batched receiver between `min_size`
+// and `max_size` after every handled batch, starting from its `batch_size`.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdaptiveBatch {
+    pub target: AdaptiveTarget,
+    pub min_size: usize,
+    pub max_size: usize,
+}
+
+impl AdaptiveBatch {
+    pub fn latency(target: Duration, min_size: usize, max_size: usize) -> Self {
+        Self {
+            target: AdaptiveTarget::Latency(target),
+            min_size,
+            max_size,
+        }
+    }
+
+    pub fn throughput(min_size: usize, max_size: usize) -> Self {
+        Self {
+            target: AdaptiveTarget::Throughput,
+            min_size,
+            max_size,
+        }
+    }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.target != AdaptiveTarget::Disabled
+    }
+
+    fn bounds(&self) -> (usize, usize) {
+        let min = self.min_size.max(1);
+
+        (min, self.max_size.max(min))
+    }
+}
+
+// last measured throughput and the direction the size is moving in
+struct Climb {
+    throughput: f64,
+    grow: bool,
+}
+
+// The effective batch size of a batched receiver, shared by its poller, which
+// cuts the batches, and the tasks handling them, which report how long the
+// handler took.
+pub(crate) struct BatchSizer {
+    cfg: AdaptiveBatch,
+    size: AtomicUsize,
+    climb: Mutex<Climb>,
+}
+
+impl BatchSizer {
+    pub fn new(batch_size: usize, cfg: AdaptiveBatch) -> Self {
+        let size = if cfg.is_enabled() {
+            let (min, max) = cfg.bounds();
+            batch_size.clamp(min, max)
+        } else {
+            batch_size
+        };
+
+        Self {
+            cfg,
+            size: AtomicUsize::new(size),
+            climb: Mutex::new(Climb {
+                throughput: 0.0,
+                grow: true,
+            }),
+        }
+    }
+
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.size.load(Ordering::Relaxed)
+    }
+
+    // the largest size a batch can get to
+    pub fn capacity(&self) -> usize {
+        if self.cfg.is_enabled() {
+            self.cfg.bounds().1
+        } else {
+            self.size()
+        }
+    }
+
+    // a batch of `len` messages was handled successfully in `elapsed`
+    pub fn record(&self, len: usize, elapsed: Duration) {
+        if len == 0 {
+            return;
+        }
+
+        let size = self.size();
+        let next = match self.cfg.target {
+            AdaptiveTarget::Disabled => return,
+
+            AdaptiveTarget::Latency(target) => {
+                // as many messages as fit in the target at this batch's pace,
+                // moving at most by a factor of two at once
+                let per_msg = elapsed.as_secs_f64() / len as f64;
+                let fit = target.as_secs_f64() / per_msg.max(f64::EPSILON);
+
+                fit.clamp(size as f64 / 2.0, size as f64 * 2.0) as usize
+            }
+
+            AdaptiveTarget::Throughput => {
+                // partial batches are cut by the lack of traffic, they tell
+                // nothing about the handler
+                if len < size {
+                    return;
+                }
+
+                // keep moving while the throughput improves, turn back when
+                // it drops
+                let throughput = len as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+                let mut climb = self.climb.lock();
+                if throughput < climb.throughput {
+                    climb.grow = !climb.grow;
+                }
+                climb.throughput = throughput;
+
+                let step = (size / 8).max(1);
+                if climb.grow {
+                    size + step
+                } else {
+                    size.saturating_sub(step)
+                }
+            }
+        };
+
+        let (min, max) = self.cfg.bounds();
+        self.size.store(next.clamp(min, max), Ordering::Relaxed);
+    }
+}
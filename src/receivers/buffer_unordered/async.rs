@@ -14,7 +14,7 @@ use crate::{
         Action, Event, ReciveTypedReceiver, SendTypedReceiver, SendUntypedReceiver,
         UntypedPollerCallback,
     },
-    receivers::Request,
+    receivers::{retry_async, CircuitBreakerConfig, OverflowPolicy, RateLimit, Request},
     AsyncHandler, Bus, Message, Untyped,
 };
 
@@ -27,11 +27,34 @@ use tokio::sync::mpsc::{self, UnboundedSender};
 buffer_unordered_poller_macro!(
     T,
     AsyncHandler,
-    |mid, msg, bus, ut: Arc<T>, stx: UnboundedSender<_>, task_permit| {
+    |mid, msg, bus: Bus, ut: Arc<T>, stx: UnboundedSender<_>, task_permit, retry, rid| {
         tokio::spawn(async move {
-            let resp = ut.handle(msg, &bus).await;
+            let (resp, attempts, dead) = retry_async(
+                retry,
+                &bus,
+                || bus.is_live(),
+                msg,
+                M::try_clone,
+                bus.wants_dead_letters::<M>(),
+                |msg| ut.handle(msg, &bus),
+            )
+            .await;
+
+            let dead_letters = resp
+                .as_ref()
+                .err()
+                .map(|err| bus.send_dead_letters::<T, _, _>(dead, err, attempts));
+
+            bus.after_handle::<M, _>(rid, resp.as_ref().err()).await;
+
+            // the slot is given back before the dead letters are sent, they
+            // may wait for a full dead-letter receiver
             drop(task_permit);
 
+            if let Some(dead_letters) = dead_letters {
+                dead_letters.await;
+            }
+
             stx.send(Event::Response(mid, resp.map_err(Error::Other)))
                 .unwrap();
         })
@@ -59,6 +82,22 @@ where
 {
     type Config = BufferUnorderedConfig;
 
+    fn circuit_breaker(cfg: &Self::Config) -> CircuitBreakerConfig {
+        cfg.circuit_breaker
+    }
+
+    fn rate_limit(cfg: &Self::Config) -> RateLimit {
+        cfg.rate_limit
+    }
+
+    fn overflow(cfg: &Self::Config) -> OverflowPolicy {
+        cfg.overflow
+    }
+
+    fn resend_unused_resp(cfg: &Self::Config) -> bool {
+        cfg.resend_unused_resp
+    }
+
     fn build(cfg: Self::Config) -> (Self, UntypedPollerCallback) {
         let stats = Arc::new(BufferUnorderedStats {
             buffer: AtomicU64::new(0),
@@ -116,14 +155,14 @@ where
     R: Message,
     E: StdSyncSendError,
 {
-    fn send(&self, mid: u64, m: M, req: bool, _bus: &Bus) -> Result<(), Error<M>> {
-        match self.tx.send(Request::Request(mid, m, req)) {
+    fn send(&self, mid: u64, m: M, req: bool, bus: &Bus) -> Result<(), Error<M>> {
+        match self.tx.send(Request::Request(mid, m, req, bus.context())) {
             Ok(_) => {
                 self.stats.buffer.fetch_add(1, Ordering::Relaxed);
 
                 Ok(())
             }
-            Err(mpsc::error::SendError(Request::Request(_, msg, _))) => {
+            Err(mpsc::error::SendError(Request::Request(_, msg, _, _))) => {
                 Err(Error::send_closed(msg))
             }
             _ => unimplemented!(),
@@ -7,6 +7,8 @@ pub use r#async::BufferUnorderedAsync;
 use serde_derive::{Deserialize, Serialize};
 pub use sync::BufferUnorderedSync;
 
+use super::{CircuitBreakerConfig, OverflowPolicy, PartitionFn, RateLimit, RetryPolicy};
+
 #[derive(Debug)]
 pub struct BufferUnorderedStats {
     pub buffer: AtomicU64,
@@ -19,6 +21,21 @@ pub struct BufferUnorderedStats {
 pub struct BufferUnorderedConfig {
     pub buffer_size: usize,
     pub max_parallel: usize,
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+    #[serde(default)]
+    pub rate_limit: RateLimit,
+    #[serde(default)]
+    pub overflow: OverflowPolicy,
+    // publish `Ok` responses nobody waits for as new messages
+    #[serde(default)]
+    pub resend_unused_resp: bool,
+    // messages with the same key are handled in order, one at a time, while
+    // different keys still run in parallel
+    #[serde(skip)]
+    pub partition_key: Option<PartitionFn>,
 }
 
 impl Default for BufferUnorderedConfig {
@@ -26,6 +43,12 @@ impl Default for BufferUnorderedConfig {
         Self {
             buffer_size: 8,
             max_parallel: 8,
+            retry: RetryPolicy::default(),
+            circuit_breaker: CircuitBreakerConfig::default(),
+            rate_limit: RateLimit::default(),
+            overflow: OverflowPolicy::default(),
+            resend_unused_resp: false,
+            partition_key: None,
         }
     }
 }
@@ -48,22 +71,138 @@ macro_rules! buffer_unordered_poller_macro {
         {
             let ut = ut.downcast::<$t>().unwrap();
             let semaphore = Arc::new(tokio::sync::Semaphore::new(cfg.max_parallel));
+            let mut receiver_id = 0;
+
+            // per partition key with a message in flight, the messages queued
+            // behind it; each key's next message is started once the previous
+            // one reports done
+            let mut partitions: std::collections::HashMap<
+                u64,
+                std::collections::VecDeque<Request<M>>,
+            > = std::collections::HashMap::new();
+            let (done_tx, mut done_rx) = mpsc::unbounded_channel::<u64>();
+
+            // a flush or sync waits for the partitions to drain, the messages
+            // sent after it wait behind it
+            let mut held = None;
+            let mut closed = false;
+
+            loop {
+                let (msg, dequeued) = if held.is_some() && partitions.is_empty() {
+                    (held.take().unwrap(), false)
+                } else {
+                    tokio::select! {
+                        msg = rx.recv(), if held.is_none() && !closed => match msg {
+                            Some(msg @ Request::Action(Action::Flush | Action::Sync))
+                                if !partitions.is_empty() =>
+                            {
+                                held = Some(msg);
+                                continue;
+                            }
+                            Some(msg) => (msg, false),
+                            None => {
+                                closed = true;
+                                continue;
+                            }
+                        },
+                        Some(key) = done_rx.recv(), if !partitions.is_empty() => {
+                            let queued = partitions.get_mut(&key).and_then(|q| q.pop_front());
+                            match queued {
+                                Some(msg) => (msg, true),
+                                None => {
+                                    partitions.remove(&key);
+                                    continue;
+                                }
+                            }
+                        },
+                        else => break,
+                    }
+                };
 
-            while let Some(msg) = rx.recv().await {
                 match msg {
-                    Request::Request(mid, msg, _req) => {
+                    Request::Request(mid, mut msg, req, ctx) if cfg.partition_key.is_some() => {
+                        let key = (cfg.partition_key.unwrap())(&msg);
+                        if !dequeued {
+                            match partitions.entry(key) {
+                                std::collections::hash_map::Entry::Occupied(mut queue) => {
+                                    queue
+                                        .get_mut()
+                                        .push_back(Request::Request(mid, msg, req, ctx));
+                                    continue;
+                                }
+                                std::collections::hash_map::Entry::Vacant(entry) => {
+                                    entry.insert(Default::default());
+                                }
+                            }
+                        }
+
+                        let bus = bus.with_context(ctx);
+                        let task_permit = semaphore.clone().acquire_owned().await;
+
+                        if let Err(err) = bus.start_message() {
+                            stx.send(Event::Response(mid, Err(err))).unwrap();
+                            done_tx.send(key).unwrap();
+                            continue;
+                        }
+
+                        if let Err(err) = bus.before_handle(receiver_id, &mut msg).await {
+                            stx.send(Event::Response(mid, Err(Error::OtherBoxed(err))))
+                                .unwrap();
+                            done_tx.send(key).unwrap();
+                            continue;
+                        }
+
+                        #[allow(clippy::redundant_closure_call)]
+                        let handle = ($st1)(
+                            mid,
+                            msg,
+                            bus,
+                            ut.clone(),
+                            stx.clone(),
+                            task_permit,
+                            cfg.retry,
+                            receiver_id,
+                        );
+
+                        let done_tx = done_tx.clone();
+                        tokio::spawn(async move {
+                            let _ = handle.await;
+                            let _ = done_tx.send(key);
+                        });
+                    }
+
+                    Request::Request(mid, mut msg, _req, ctx) => {
+                        let bus = bus.with_context(ctx);
+                        let task_permit = semaphore.clone().acquire_owned().await;
+
+                        if let Err(err) = bus.start_message() {
+                            stx.send(Event::Response(mid, Err(err))).unwrap();
+                            continue;
+                        }
+
+                        if let Err(err) = bus.before_handle(receiver_id, &mut msg).await {
+                            stx.send(Event::Response(mid, Err(Error::OtherBoxed(err))))
+                                .unwrap();
+                            continue;
+                        }
+
                         #[allow(clippy::redundant_closure_call)]
                         let _ = ($st1)(
                             mid,
                             msg,
-                            bus.clone(),
+                            bus,
                             ut.clone(),
                             stx.clone(),
-                            semaphore.clone().acquire_owned().await,
+                            task_permit,
+                            cfg.retry,
+                            receiver_id,
                         );
                     }
 
-                    Request::Action(Action::Init(..)) => stx.send(Event::Ready).unwrap(),
+                    Request::Action(Action::Init(id)) => {
+                        receiver_id = id;
+                        stx.send(Event::Ready).unwrap();
+                    }
                     Request::Action(Action::Close) => rx.close(),
 
                     Request::Action(Action::Flush) => {
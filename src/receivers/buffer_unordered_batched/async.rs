@@ -14,23 +14,70 @@ use crate::{
         Action, Event, ReciveTypedReceiver, SendTypedReceiver, SendUntypedReceiver,
         UntypedPollerCallback,
     },
-    receivers::Request,
+    receivers::{
+        retry_async, try_clone_batch, BatchSizer, CircuitBreakerConfig, OverflowPolicy, RateLimit,
+        Request,
+    },
     AsyncBatchHandler, Bus, Message, Untyped,
 };
 
 use super::{BufferUnorderedBatchedConfig, BufferUnorderedBatchedStats};
 use futures::{Future, Stream};
 use parking_lot::Mutex;
-use tokio::sync::mpsc::{self, UnboundedSender};
+use tokio::{
+    sync::mpsc::{self, UnboundedSender},
+    time::Instant,
+};
 
 buffer_unordered_batch_poller_macro!(
     T,
     AsyncBatchHandler,
-    |mids: Vec<_>, msgs, bus, ut: Arc<T>, task_permit, stx: UnboundedSender<_>| {
+    |mids: Vec<_>,
+     msgs,
+     bus: Bus,
+     ut: Arc<T>,
+     task_permit,
+     stx: UnboundedSender<_>,
+     retry,
+     rid,
+     sizer: Arc<BatchSizer>| {
         tokio::spawn(async move {
-            let resp = ut.handle(msgs, &bus).await;
+            let (resp, attempts, dead) = retry_async(
+                retry,
+                &bus,
+                || bus.is_batch_live(&mids),
+                msgs,
+                try_clone_batch,
+                bus.wants_dead_letters::<M>(),
+                |msgs| {
+                    let (ut, bus, sizer) = (&ut, &bus, &sizer);
+                    async move {
+                        let (len, started) = (msgs.len(), Instant::now());
+                        let resp = ut.handle(msgs.into_iter().collect(), bus).await;
+                        if resp.is_ok() {
+                            sizer.record(len, started.elapsed());
+                        }
+
+                        resp
+                    }
+                },
+            )
+            .await;
+
+            let dead_letters = resp.as_ref().err().map(|err| {
+                bus.send_dead_letters::<T, _, _>(dead.into_iter().flatten(), err, attempts)
+            });
+
+            bus.after_handle::<M, _>(rid, resp.as_ref().err()).await;
+
+            // the slot is given back before the dead letters are sent, they
+            // may wait for a full dead-letter receiver
             drop(task_permit);
 
+            if let Some(dead_letters) = dead_letters {
+                dead_letters.await;
+            }
+
             crate::process_batch_result!(resp, mids, stx);
         })
     },
@@ -45,6 +92,7 @@ where
 {
     tx: mpsc::UnboundedSender<Request<M>>,
     stats: Arc<BufferUnorderedBatchedStats>,
+    sizer: Arc<BatchSizer>,
     srx: Mutex<Option<mpsc::UnboundedReceiver<Event<R, E>>>>,
 }
 
@@ -58,6 +106,22 @@ where
 {
     type Config = BufferUnorderedBatchedConfig;
 
+    fn circuit_breaker(cfg: &Self::Config) -> CircuitBreakerConfig {
+        cfg.circuit_breaker
+    }
+
+    fn rate_limit(cfg: &Self::Config) -> RateLimit {
+        cfg.rate_limit
+    }
+
+    fn overflow(cfg: &Self::Config) -> OverflowPolicy {
+        cfg.overflow
+    }
+
+    fn resend_unused_resp(cfg: &Self::Config) -> bool {
+        cfg.resend_unused_resp
+    }
+
     fn build(cfg: Self::Config) -> (Self, UntypedPollerCallback) {
         let stats = Arc::new(BufferUnorderedBatchedStats {
             buffer: AtomicU64::new(0),
@@ -71,6 +135,8 @@ where
         let (stx, srx) = mpsc::unbounded_channel();
         let (tx, rx) = mpsc::unbounded_channel();
         let stats_clone = stats.clone();
+        let sizer = Arc::new(BatchSizer::new(cfg.batch_size, cfg.adaptive));
+        let sizer_clone = sizer.clone();
 
         let poller = Box::new(move |ut| {
             Box::new(move |bus| {
@@ -79,6 +145,7 @@ where
                     bus,
                     ut,
                     stats_clone,
+                    sizer_clone,
                     cfg,
                     stx,
                 )) as Pin<Box<dyn Future<Output = ()> + Send>>
@@ -89,6 +156,7 @@ where
             BufferUnorderedBatchedAsync::<M, R, T::Error> {
                 tx,
                 stats,
+                sizer,
                 srx: Mutex::new(Some(srx)),
             },
             poller,
@@ -109,6 +177,10 @@ where
             _ => unimplemented!(),
         }
     }
+
+    fn batch_stats(&self) -> Option<(usize, usize)> {
+        Some((self.sizer.capacity(), self.sizer.size()))
+    }
 }
 
 impl<M, R, E> SendTypedReceiver<M> for BufferUnorderedBatchedAsync<M, R, E>
@@ -117,14 +189,14 @@ where
     R: Message,
     E: StdSyncSendError,
 {
-    fn send(&self, mid: u64, m: M, req: bool, _bus: &Bus) -> Result<(), Error<M>> {
-        match self.tx.send(Request::Request(mid, m, req)) {
+    fn send(&self, mid: u64, m: M, req: bool, bus: &Bus) -> Result<(), Error<M>> {
+        match self.tx.send(Request::Request(mid, m, req, bus.context())) {
             Ok(_) => {
                 self.stats.buffer.fetch_add(1, Ordering::Relaxed);
 
                 Ok(())
             }
-            Err(mpsc::error::SendError(Request::Request(_, msg, _))) => {
+            Err(mpsc::error::SendError(Request::Request(_, msg, _, _))) => {
                 Err(Error::send_closed(msg))
             }
             _ => unimplemented!(),
@@ -1,12 +1,14 @@
 mod r#async;
 mod sync;
 
-use std::sync::atomic::AtomicU64;
+use std::{sync::atomic::AtomicU64, time::Duration};
 
 pub use r#async::BufferUnorderedBatchedAsync;
 use serde_derive::{Deserialize, Serialize};
 pub use sync::BufferUnorderedBatchedSync;
 
+use super::{AdaptiveBatch, CircuitBreakerConfig, OverflowPolicy, RateLimit, RetryPolicy};
+
 #[derive(Debug)]
 pub struct BufferUnorderedBatchedStats {
     pub buffer: AtomicU64,
@@ -23,6 +25,28 @@ pub struct BufferUnorderedBatchedConfig {
     pub max_parallel: usize,
     pub batch_size: usize,
     pub when_ready: bool,
+    // a partial batch is dispatched once its oldest message waited this long,
+    // zero waits for a full batch or a flush
+    #[serde(default)]
+    pub max_linger: Duration,
+    // partial batches smaller than this are not dispatched on linger; only
+    // used with `max_linger`, a full batch or a flush dispatches any size
+    #[serde(default)]
+    pub min_batch_size: usize,
+    // tunes the batch size to the handler instead of keeping `batch_size`
+    #[serde(default)]
+    pub adaptive: AdaptiveBatch,
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+    #[serde(default)]
+    pub rate_limit: RateLimit,
+    #[serde(default)]
+    pub overflow: OverflowPolicy,
+    // publish `Ok` responses nobody waits for as new messages
+    #[serde(default)]
+    pub resend_unused_resp: bool,
 }
 
 impl Default for BufferUnorderedBatchedConfig {
@@ -32,6 +56,14 @@ impl Default for BufferUnorderedBatchedConfig {
             max_parallel: 2,
             batch_size: 8,
             when_ready: false,
+            max_linger: Duration::ZERO,
+            min_batch_size: 0,
+            adaptive: AdaptiveBatch::default(),
+            retry: RetryPolicy::default(),
+            circuit_breaker: CircuitBreakerConfig::default(),
+            rate_limit: RateLimit::default(),
+            overflow: OverflowPolicy::default(),
+            resend_unused_resp: false,
         }
     }
 }
@@ -44,6 +76,7 @@ macro_rules! buffer_unordered_batch_poller_macro {
             bus: Bus,
             ut: Untyped,
             _stats: Arc<BufferUnorderedBatchedStats>,
+            sizer: Arc<BatchSizer>,
             cfg: BufferUnorderedBatchedConfig,
             stx: mpsc::UnboundedSender<Event<R, $t::Error>>,
         ) where
@@ -55,54 +88,141 @@ macro_rules! buffer_unordered_batch_poller_macro {
             let semaphore = Arc::new(tokio::sync::Semaphore::new(cfg.max_parallel));
 
             let mut buffer_mid = Vec::with_capacity(cfg.batch_size);
+            let mut receiver_id = 0;
             let mut buffer = Vec::with_capacity(cfg.batch_size);
+            let mut linger_at = None;
+
+            loop {
+                let linger = linger_at.filter(|_| buffer.len() >= cfg.min_batch_size);
+                let msg = tokio::select! {
+                    msg = rx.recv() => match msg {
+                        Some(msg) => Some(msg),
+                        None => break,
+                    },
+                    _ = tokio::time::sleep_until(linger.unwrap_or_else(tokio::time::Instant::now)),
+                        if linger.is_some() => None,
+                };
 
-            while let Some(msg) = rx.recv().await {
                 let bus = bus.clone();
                 let ut = ut.clone();
                 let semaphore = semaphore.clone();
                 let stx = stx.clone();
 
                 match msg {
-                    Request::Request(mid, msg, req) => {
-                        buffer_mid.push((mid, req));
-                        buffer.push(msg);
+                    // the oldest buffered message lingered for `max_linger`
+                    None => {
+                        linger_at = None;
 
-                        if buffer_mid.len() >= cfg.batch_size {
-                            let task_permit = semaphore.acquire_owned().await;
+                        let task_permit = semaphore.acquire_owned().await;
 
-                            let buffer_mid_clone = buffer_mid.drain(..).collect::<Vec<_>>();
-                            let buffer_clone = buffer.drain(..).collect();
+                        let (buffer_mid_clone, buffer_clone) =
+                            $crate::receivers::cut_batch(&bus, &mut buffer_mid, &mut buffer, &stx);
 
+                        if !buffer_mid_clone.is_empty() {
                             #[allow(clippy::redundant_closure_call)]
-                            let _ =
-                                ($st1)(buffer_mid_clone, buffer_clone, bus, ut, task_permit, stx);
+                            drop(($st1)(
+                                buffer_mid_clone,
+                                buffer_clone,
+                                bus,
+                                ut,
+                                task_permit,
+                                stx,
+                                cfg.retry,
+                                receiver_id,
+                                sizer.clone(),
+                            ));
                         }
                     }
-                    Request::Action(Action::Init(..)) => {
+                    Some(Request::Request(mid, mut msg, req, ctx)) => {
+                        let msg_bus = bus.with_context(ctx.clone());
+
+                        if let Err(err) = msg_bus.start_message() {
+                            stx.send(Event::Response(mid, Err(err))).unwrap();
+                            continue;
+                        }
+
+                        if let Err(err) = msg_bus.before_handle(receiver_id, &mut msg).await {
+                            stx.send(Event::Response(mid, Err(Error::OtherBoxed(err))))
+                                .unwrap();
+                            continue;
+                        }
+
+                        buffer_mid.push((mid, req, ctx));
+                        buffer.push(msg);
+
+                        if buffer_mid.len() == 1 && !cfg.max_linger.is_zero() {
+                            linger_at = Some(tokio::time::Instant::now() + cfg.max_linger);
+                        }
+
+                        if buffer_mid.len() >= sizer.size() {
+                            linger_at = None;
+                            let task_permit = semaphore.acquire_owned().await;
+
+                            let (buffer_mid_clone, buffer_clone) = $crate::receivers::cut_batch(
+                                &bus,
+                                &mut buffer_mid,
+                                &mut buffer,
+                                &stx,
+                            );
+
+                            if !buffer_mid_clone.is_empty() {
+                                #[allow(clippy::redundant_closure_call)]
+                                drop(($st1)(
+                                    buffer_mid_clone,
+                                    buffer_clone,
+                                    bus,
+                                    ut,
+                                    task_permit,
+                                    stx,
+                                    cfg.retry,
+                                    receiver_id,
+                                    sizer.clone(),
+                                ));
+                            }
+                        }
+                    }
+                    Some(Request::Action(Action::Init(id))) => {
+                        receiver_id = id;
                         stx.send(Event::Ready).unwrap();
                     }
-                    Request::Action(Action::Close) => {
+                    Some(Request::Action(Action::Close)) => {
                         rx.close();
                     }
-                    Request::Action(Action::Flush) => {
+                    Some(Request::Action(Action::Flush)) => {
                         let stx_clone = stx.clone();
 
+                        linger_at = None;
+
                         if !buffer_mid.is_empty() {
-                            let buffer_mid_clone = buffer_mid.drain(..).collect::<Vec<_>>();
-                            let buffer_clone = buffer.drain(..).collect();
                             let task_permit = semaphore.clone().acquire_owned().await;
-
-                            #[allow(clippy::redundant_closure_call)]
-                            let _ =
-                                ($st1)(buffer_mid_clone, buffer_clone, bus, ut, task_permit, stx);
+                            let (buffer_mid_clone, buffer_clone) = $crate::receivers::cut_batch(
+                                &bus,
+                                &mut buffer_mid,
+                                &mut buffer,
+                                &stx,
+                            );
+
+                            if !buffer_mid_clone.is_empty() {
+                                #[allow(clippy::redundant_closure_call)]
+                                drop(($st1)(
+                                    buffer_mid_clone,
+                                    buffer_clone,
+                                    bus,
+                                    ut,
+                                    task_permit,
+                                    stx,
+                                    cfg.retry,
+                                    receiver_id,
+                                    sizer.clone(),
+                                ));
+                            }
                         }
 
                         let _ = semaphore.acquire_many(cfg.max_parallel as _).await;
                         stx_clone.send(Event::Flushed).unwrap();
                     }
 
-                    Request::Action(Action::Sync) => {
+                    Some(Request::Action(Action::Sync)) => {
                         let lock = semaphore.acquire_many(cfg.max_parallel as _).await;
 
                         #[allow(clippy::redundant_closure_call)]
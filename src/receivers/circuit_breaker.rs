@@ -0,0 +1,164 @@
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use serde_derive::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CircuitState {
+    #[default]
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+// Set per subscription of a local receiver. Relays have no breaker: the
+// handlers behind them fail on the remote bus, which keeps its own breakers.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CircuitBreakerConfig {
+    // consecutive handler errors that open the circuit, `0` disables the breaker
+    pub failure_threshold: u32,
+    // the errors have to happen within this window, counted from the first one
+    pub window: Duration,
+    // how long the circuit stays open before a trial message is let through
+    pub cooldown: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 0,
+            window: Duration::from_secs(10),
+            cooldown: Duration::from_secs(5),
+        }
+    }
+}
+
+impl CircuitBreakerConfig {
+    pub fn new(failure_threshold: u32) -> Self {
+        Self {
+            failure_threshold,
+            ..Default::default()
+        }
+    }
+
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    pub fn cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.failure_threshold > 0
+    }
+}
+
+struct BreakerState {
+    state: CircuitState,
+    failures: u32,
+    first_failure: Instant,
+    opened_at: Instant,
+}
+
+pub(crate) struct CircuitBreaker {
+    cfg: CircuitBreakerConfig,
+    inner: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    pub fn new(cfg: CircuitBreakerConfig) -> Self {
+        let now = Instant::now();
+
+        Self {
+            cfg,
+            inner: Mutex::new(BreakerState {
+                state: CircuitState::Closed,
+                failures: 0,
+                first_failure: now,
+                opened_at: now,
+            }),
+        }
+    }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.cfg.is_enabled()
+    }
+
+    pub fn state(&self) -> CircuitState {
+        self.inner.lock().state
+    }
+
+    // called for every message about to be sent to the receiver, before its
+    // permit and overflow policy are dealt with; once the cool-down is over
+    // messages are let through again
+    pub fn allow(&self) -> bool {
+        if !self.is_enabled() {
+            return true;
+        }
+
+        let inner = self.inner.lock();
+        match inner.state {
+            CircuitState::Closed => true,
+            CircuitState::Open | CircuitState::HalfOpen => {
+                inner.opened_at.elapsed() >= self.cfg.cooldown
+            }
+        }
+    }
+
+    // called once the receiver has taken the message; after the cool-down the
+    // first one is the trial. A trial without an outcome (cancelled, expired
+    // or refused by a middleware) is given up on after another cool-down, and
+    // the next message becomes the trial
+    pub fn enter(&self) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let mut inner = self.inner.lock();
+        if inner.state != CircuitState::Closed && inner.opened_at.elapsed() >= self.cfg.cooldown {
+            inner.state = CircuitState::HalfOpen;
+            inner.opened_at = Instant::now();
+        }
+    }
+
+    pub fn record(&self, ok: bool) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let mut inner = self.inner.lock();
+        match (inner.state, ok) {
+            (CircuitState::Closed, true) => inner.failures = 0,
+            (CircuitState::Closed, false) => {
+                if inner.failures == 0 || inner.first_failure.elapsed() > self.cfg.window {
+                    inner.failures = 0;
+                    inner.first_failure = Instant::now();
+                }
+
+                inner.failures += 1;
+                if inner.failures >= self.cfg.failure_threshold {
+                    inner.state = CircuitState::Open;
+                    inner.opened_at = Instant::now();
+                }
+            }
+            (CircuitState::HalfOpen, true) => {
+                inner.state = CircuitState::Closed;
+                inner.failures = 0;
+            }
+            (CircuitState::HalfOpen, false) => {
+                inner.state = CircuitState::Open;
+                inner.opened_at = Instant::now();
+            }
+
+            // results of messages accepted before the circuit opened
+            (CircuitState::Open, _) => (),
+        }
+    }
+}
@@ -1,6 +1,13 @@
+mod adaptive;
 mod buffer_unordered;
 mod buffer_unordered_batched;
-// mod producer;
+mod circuit_breaker;
+mod overflow;
+mod partition;
+mod producer;
+mod rate_limit;
+mod retry;
+mod sharded;
 mod synchronize_batched;
 mod synchronized;
 
@@ -14,9 +21,26 @@ pub use synchronize_batched::{
     SynchronizedBatchedAsync, SynchronizedBatchedConfig, SynchronizedBatchedSync,
 };
 
-// pub use producer::{AsyncProducer, AsyncProducerConfig};
+pub use adaptive::{AdaptiveBatch, AdaptiveTarget};
+pub use circuit_breaker::{CircuitBreakerConfig, CircuitState};
+pub use overflow::OverflowPolicy;
+pub use partition::{partition_by, PartitionFn, Partitioned};
+pub use producer::{AsyncProducer, AsyncProducerConfig};
+pub use rate_limit::RateLimit;
+pub use retry::{RetryPolicy, RetryPredicate};
 
-use crate::receiver::Action;
+pub(crate) use adaptive::BatchSizer;
+pub(crate) use circuit_breaker::CircuitBreaker;
+pub(crate) use overflow::EvictionQueue;
+pub(crate) use rate_limit::TokenBucket;
+pub(crate) use retry::{retry_async, retry_blocking, try_clone_batch};
+pub(crate) use sharded::{Sharded, ShardedConfig};
+
+use std::sync::Arc;
+
+use tokio::sync::mpsc;
+
+use crate::{context::Context, error::StdSyncSendError, receiver::Action, Bus, Event};
 
 #[macro_export]
 macro_rules! process_batch_result {
@@ -28,7 +52,7 @@ macro_rules! process_batch_result {
                 let mut mids = mids.into_iter();
                 let mut re = re.into_iter();
 
-                while let Some((mid, _req)) = mids.next() {
+                while let Some((mid, _req, _ctx)) = mids.next() {
                     if let Some(r) = re.next() {
                         $stx.send(Event::Response(mid, Ok(r))).unwrap();
                     } else {
@@ -38,7 +62,7 @@ macro_rules! process_batch_result {
                 }
             }
             Err(er) => {
-                for (mid, _req) in mids {
+                for (mid, _req, _ctx) in mids {
                     $stx.send(Event::Response(mid, Err(Error::Other(er.clone()))))
                         .unwrap();
                 }
@@ -52,5 +76,34 @@ macro_rules! process_batch_result {
 #[derive(Debug)]
 pub(crate) enum Request<M> {
     Action(Action),
-    Request(u64, M, bool),
+    Request(u64, M, bool, Option<Arc<Context>>),
+}
+
+// A batched message as buffered by its receiver: id, whether it is a request
+// and its context.
+pub(crate) type BatchedMessage = (u64, bool, Option<Arc<Context>>);
+
+// Takes the buffered messages out as a batch, leaving out the ones cancelled
+// or expired while they waited for it; those are answered right away.
+pub(crate) fn cut_batch<M, R, E: StdSyncSendError>(
+    bus: &Bus,
+    buffer_mid: &mut Vec<BatchedMessage>,
+    buffer: &mut Vec<M>,
+    stx: &mpsc::UnboundedSender<Event<R, E>>,
+) -> (Vec<BatchedMessage>, Vec<M>) {
+    let mut mids = Vec::with_capacity(buffer_mid.len());
+    let mut msgs = Vec::with_capacity(buffer.len());
+
+    for ((mid, req, ctx), msg) in buffer_mid.drain(..).zip(buffer.drain(..)) {
+        match bus.with_context(ctx.clone()).recheck_message() {
+            Ok(()) => {
+                mids.push((mid, req, ctx));
+                msgs.push(msg);
+            }
+
+            Err(err) => stx.send(Event::Response(mid, Err(err))).unwrap(),
+        }
+    }
+
+    (mids, msgs)
 }
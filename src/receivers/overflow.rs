@@ -0,0 +1,78 @@
+use core::sync::atomic::{AtomicU64, Ordering};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Weak},
+};
+
+use parking_lot::Mutex;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::context::Context;
+
+// What a receiver does with a message sent while its queue is full.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum OverflowPolicy {
+    // `send` waits for capacity, `try_send` fails with `Full` and
+    // `force_send` ignores the limit
+    #[default]
+    Block,
+    // every send fails with `Full` right away
+    Reject,
+    // the oldest queued message not yet picked up by the handler is dropped
+    // to make room for the new one
+    DropOldest,
+    // the new message is dropped
+    DropNewest,
+}
+
+impl OverflowPolicy {
+    #[inline]
+    pub fn is_blocking(&self) -> bool {
+        matches!(self, OverflowPolicy::Block)
+    }
+}
+
+// Contexts of the messages queued on a `DropOldest` receiver, oldest first.
+// Weak references, so a handled message does not outlive its handling.
+#[derive(Default)]
+pub(crate) struct EvictionQueue {
+    queued: Mutex<VecDeque<Weak<Context>>>,
+    // evicted messages the receiver has not skipped yet
+    evicted: AtomicU64,
+}
+
+impl EvictionQueue {
+    pub fn push(&self, ctx: &Arc<Context>) {
+        let mut queued = self.queued.lock();
+
+        while queued
+            .front()
+            .is_some_and(|front| front.upgrade().is_none_or(|ctx| ctx.is_picked()))
+        {
+            queued.pop_front();
+        }
+
+        queued.push_back(Arc::downgrade(ctx));
+    }
+
+    pub fn evict(&self) -> bool {
+        let mut queued = self.queued.lock();
+
+        while let Some(front) = queued.pop_front() {
+            if front.upgrade().is_some_and(|ctx| ctx.evict()) {
+                self.evicted.fetch_add(1, Ordering::SeqCst);
+                return true;
+            }
+        }
+
+        false
+    }
+
+    // called for a message the receiver skipped as cancelled; true if it
+    // stands for an eviction, which was counted as a drop already
+    pub fn skipped(&self) -> bool {
+        self.evicted
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_ok()
+    }
+}
@@ -0,0 +1,30 @@
+use core::hash::{Hash, Hasher};
+use std::collections::hash_map::DefaultHasher;
+
+use crate::Message;
+
+// Extracts the partition of a message: messages of one partition are handled
+// one at a time, in the order they were sent.
+pub type PartitionFn = fn(&dyn Message) -> u64;
+
+pub trait Partitioned: Message {
+    type Key: Hash;
+
+    fn partition_key(&self) -> Self::Key;
+}
+
+// partition function hashing `M::partition_key`; messages of other types all
+// fall into the same partition
+pub fn partition_by<M: Partitioned>() -> PartitionFn {
+    |msg| {
+        msg.as_any_ref()
+            .downcast_ref::<M>()
+            .map_or(0, |msg| hash_key(&msg.partition_key()))
+    }
+}
+
+pub(crate) fn hash_key<K: Hash>(key: &K) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
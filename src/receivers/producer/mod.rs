@@ -1,20 +1,31 @@
 use std::pin::Pin;
 
-use futures::{pin_mut, Future, Stream};
-use tokio::sync::{mpsc, Mutex};
+use futures::{Future, Stream, StreamExt};
+use serde_derive::{Deserialize, Serialize};
+use tokio::sync::mpsc;
 
 use crate::builder::ReceiverSubscriberBuilder;
 use crate::error::{Error, StdSyncSendError};
-use crate::handler::AsyncProducer as AsyncProducerHandler;
+use crate::handler::{AsyncProducer as AsyncProducerHandler, ProducerStats};
 use crate::receiver::UntypedPollerCallback;
-use crate::receivers::Request;
+use crate::receivers::{CircuitBreakerConfig, OverflowPolicy, RateLimit, Request};
 use crate::{
     Action, Bus, Event, Message, ReciveTypedReceiver, SendTypedReceiver, SendUntypedReceiver,
     Untyped,
 };
 
-#[derive(Default)]
-pub struct AsyncProducerConfig {}
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AsyncProducerConfig {
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+    #[serde(default)]
+    pub rate_limit: RateLimit,
+    #[serde(default)]
+    pub overflow: OverflowPolicy,
+    // publish `Ok` responses nobody waits for as new messages
+    #[serde(default)]
+    pub resend_unused_resp: bool,
+}
 
 async fn producer_poller<T, M>(
     mut rx: mpsc::UnboundedReceiver<Request<M>>,
@@ -24,23 +35,73 @@ async fn producer_poller<T, M>(
 ) where
     T: AsyncProducerHandler<M> + 'static,
     T::Error: StdSyncSendError,
-    T::Item: Message,
+    T::Item: Message + Clone,
     T::Response: Message,
     M: Message,
 {
-    let ut = ut.downcast::<Mutex<T>>().unwrap();
-    let stream = Option<Pin<Box<dyn Stream<Item = Result<Self::Item, Self::Error>> + Send + '_>>, Self::Error>;
+    let ut = ut.downcast::<T>().unwrap();
+    let mut receiver_id = 0;
 
     while let Some(msg) = rx.recv().await {
         match msg {
-            Request::Request(mid, msg, _req) => {
-                let lock = ut.lock().await;
-                let stream = lock.producer(msg, &bus).await.unwrap();
-                pin_mut!(stream);
-
-                stx.send(Event::BatchComplete(M::type_tag_(), 1)).unwrap();
+            Request::Request(mid, mut msg, _req, ctx) => {
+                let bus = bus.with_context(ctx);
+
+                if let Err(err) = bus.start_message() {
+                    stx.send(Event::Response(mid, Err(err))).unwrap();
+                    continue;
+                }
+
+                if let Err(err) = bus.before_handle(receiver_id, &mut msg).await {
+                    stx.send(Event::Response(mid, Err(Error::OtherBoxed(err))))
+                        .unwrap();
+                    continue;
+                }
+
+                let mut stream = match ut.producer(msg, &bus).await {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        bus.after_handle::<M, _>(receiver_id, Some(&err)).await;
+                        stx.send(Event::Response(mid, Err(Error::Other(err))))
+                            .unwrap();
+
+                        continue;
+                    }
+                };
+
+                let mut stats = ProducerStats {
+                    completed: 0,
+                    failed: 0,
+                };
+
+                while let Some(item) = stream.next().await {
+                    match item {
+                        Ok(item) => match bus.send(item).await {
+                            Ok(_) => stats.completed += 1,
+                            Err(err) => {
+                                error!("Producer publish error: {}", err);
+                                stats.failed += 1;
+                            }
+                        },
+
+                        Err(err) => {
+                            stx.send(Event::Error(Error::Other(err))).unwrap();
+                            stats.failed += 1;
+                        }
+                    }
+                }
+
+                drop(stream);
+
+                let resp = ut.finish(stats, &bus).await;
+                bus.after_handle::<M, _>(receiver_id, resp.as_ref().err())
+                    .await;
+
+                stx.send(Event::Response(mid, resp.map_err(Error::Other)))
+                    .unwrap();
             }
-            Request::Action(Action::Init(..)) => {
+            Request::Action(Action::Init(id)) => {
+                receiver_id = id;
                 stx.send(Event::Ready).unwrap();
             }
             Request::Action(Action::Close) => {
@@ -49,7 +110,9 @@ async fn producer_poller<T, M>(
             Request::Action(Action::Flush) => {
                 stx.send(Event::Flushed).unwrap();
             }
-            Request::Action(Action::Sync) => {}
+            Request::Action(Action::Sync) => {
+                stx.send(Event::Synchronized(Ok(()))).unwrap();
+            }
 
             _ => unimplemented!(),
         }
@@ -71,13 +134,29 @@ impl<T, M> ReceiverSubscriberBuilder<T, M, T::Response, T::Error>
     for AsyncProducer<M, T::Response, T::Error>
 where
     T: AsyncProducerHandler<M> + 'static,
-    T::Item: Message,
+    T::Item: Message + Clone,
     T::Response: Message,
     T::Error: StdSyncSendError,
     M: Message,
 {
     type Config = AsyncProducerConfig;
 
+    fn circuit_breaker(cfg: &Self::Config) -> CircuitBreakerConfig {
+        cfg.circuit_breaker
+    }
+
+    fn rate_limit(cfg: &Self::Config) -> RateLimit {
+        cfg.rate_limit
+    }
+
+    fn overflow(cfg: &Self::Config) -> OverflowPolicy {
+        cfg.overflow
+    }
+
+    fn resend_unused_resp(cfg: &Self::Config) -> bool {
+        cfg.resend_unused_resp
+    }
+
     fn build(_cfg: Self::Config) -> (Self, UntypedPollerCallback) {
         let (stx, srx) = mpsc::unbounded_channel();
         let (tx, rx) = mpsc::unbounded_channel();
@@ -112,6 +191,9 @@ where
             _ => unimplemented!(),
         }
     }
+    fn is_producer(&self) -> bool {
+        true
+    }
 }
 
 impl<M, R, E> SendTypedReceiver<M> for AsyncProducer<M, R, E>
@@ -120,10 +202,10 @@ where
     R: Message,
     E: StdSyncSendError,
 {
-    fn send(&self, mid: u64, m: M, req: bool, _bus: &Bus) -> Result<(), Error<M>> {
-        match self.tx.send(Request::Request(mid, m, req)) {
+    fn send(&self, mid: u64, m: M, req: bool, bus: &Bus) -> Result<(), Error<M>> {
+        match self.tx.send(Request::Request(mid, m, req, bus.context())) {
             Ok(_) => Ok(()),
-            Err(mpsc::error::SendError(Request::Request(_, msg, _))) => {
+            Err(mpsc::error::SendError(Request::Request(_, msg, _, _))) => {
                 Err(Error::send_closed(msg))
             }
             _ => unimplemented!(),
@@ -0,0 +1,113 @@
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use serde_derive::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateLimit {
+    // sustained rate in messages per second, `0.0` disables the limit
+    pub per_second: f64,
+    // number of messages that may be sent at once after a quiet period
+    pub burst: u32,
+}
+
+impl RateLimit {
+    pub fn new(per_second: f64) -> Self {
+        Self {
+            per_second,
+            burst: 1,
+        }
+    }
+
+    pub fn burst(mut self, burst: u32) -> Self {
+        self.burst = burst;
+        self
+    }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.per_second > 0.0
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+pub(crate) struct TokenBucket {
+    cfg: RateLimit,
+    inner: Mutex<Bucket>,
+}
+
+impl TokenBucket {
+    pub fn new(cfg: RateLimit) -> Self {
+        Self {
+            cfg,
+            inner: Mutex::new(Bucket {
+                tokens: cfg.burst.max(1) as f64,
+                updated: Instant::now(),
+            }),
+        }
+    }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.cfg.is_enabled()
+    }
+
+    fn refill(&self, bucket: &mut Bucket) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+
+        bucket.tokens =
+            (bucket.tokens + elapsed * self.cfg.per_second).min(self.cfg.burst.max(1) as f64);
+        bucket.updated = now;
+    }
+
+    pub fn try_acquire(&self) -> bool {
+        if !self.is_enabled() {
+            return true;
+        }
+
+        let mut bucket = self.inner.lock();
+        self.refill(&mut bucket);
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    // gives back a token taken by a permit that ended up unused
+    pub fn release(&self) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let mut bucket = self.inner.lock();
+        bucket.tokens = (bucket.tokens + 1.0).min(self.cfg.burst.max(1) as f64);
+    }
+
+    // time left until the next token becomes available
+    pub fn delay(&self) -> Option<Duration> {
+        if !self.is_enabled() {
+            return None;
+        }
+
+        let mut bucket = self.inner.lock();
+        self.refill(&mut bucket);
+
+        if bucket.tokens >= 1.0 {
+            None
+        } else {
+            Some(Duration::from_secs_f64(
+                (1.0 - bucket.tokens) / self.cfg.per_second,
+            ))
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use std::time::Duration;
+
+use futures::Future;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{error::StdSyncSendError, rng::Rng, Bus};
+
+pub type RetryPredicate = fn(&(dyn std::error::Error + 'static)) -> bool;
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    // total number of handler invocations, `1` disables retrying
+    pub max_attempts: u32,
+    pub backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    // fraction of each delay that is randomized away, in `0.0..=1.0`
+    pub jitter: f64,
+    #[serde(skip)]
+    pub retryable: Option<RetryPredicate>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_secs(1),
+            multiplier: 2.0,
+            jitter: 0.0,
+            retryable: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            ..Default::default()
+        }
+    }
+
+    pub fn backoff(mut self, backoff: Duration, max_backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn retry_if(mut self, predicate: RetryPredicate) -> Self {
+        self.retryable = Some(predicate);
+        self
+    }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.max_attempts > 1
+    }
+
+    pub fn is_retryable<E: StdSyncSendError>(&self, err: &E) -> bool {
+        self.retryable.is_none_or(|pred| pred(err))
+    }
+
+    // delay before the attempt following the `attempt`-th failure, before
+    // jitter is applied
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exp = self
+            .multiplier
+            .max(1.0)
+            .powi(attempt.saturating_sub(1) as i32);
+
+        Duration::try_from_secs_f64(self.backoff.as_secs_f64() * exp)
+            .map_or(self.max_backoff, |d| d.min(self.max_backoff))
+    }
+
+    // jitter is drawn from the bus rng, so a seeded bus retries reproducibly
+    fn jittered_delay(&self, attempt: u32, rng: &Rng) -> Duration {
+        let delay = self.delay(attempt);
+
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        if jitter > 0.0 {
+            let rnd = (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+            delay.mul_f64(1.0 - jitter * rnd)
+        } else {
+            delay
+        }
+    }
+
+    // the copy of `msg` held while an attempt runs: for the next attempt,
+    // or for dead-lettering should the last attempt fail
+    fn spare<M>(
+        &self,
+        attempt: u32,
+        msg: &M,
+        try_clone: fn(&M) -> Option<M>,
+        keep_last: bool,
+    ) -> Option<M> {
+        if attempt < self.max_attempts || keep_last {
+            try_clone(msg)
+        } else {
+            None
+        }
+    }
+
+    // a failed attempt that could have been retried but for the message
+    // not being cloneable
+    fn warn_not_cloneable<M, E: StdSyncSendError>(&self, attempt: u32, err: &E) {
+        if attempt < self.max_attempts && self.is_retryable(err) {
+            warn!(
+                "{} is not retried: the message can not be cloned",
+                std::any::type_name::<M>()
+            );
+        }
+    }
+}
+
+// Messages are cloned ahead of each attempt so that the handler may consume them;
+// a message that cannot be cloned is handled exactly once. Retrying stops once
+// `live` says the message was cancelled or expired. Returns the result of the
+// last attempt along with the number of attempts made and, if the last attempt
+// failed and `keep_last` is set, a copy of the message it was given.
+pub(crate) async fn retry_async<M, R, E, F, Fut>(
+    policy: RetryPolicy,
+    bus: &Bus,
+    live: impl Fn() -> bool,
+    mut msg: M,
+    try_clone: fn(&M) -> Option<M>,
+    keep_last: bool,
+    mut f: F,
+) -> (Result<R, E>, u32, Option<M>)
+where
+    E: StdSyncSendError,
+    F: FnMut(M) -> Fut,
+    Fut: Future<Output = Result<R, E>>,
+{
+    let mut attempt = 1;
+
+    loop {
+        let spare = policy.spare(attempt, &msg, try_clone, keep_last);
+
+        match (f(msg).await, spare) {
+            (Err(err), Some(next))
+                if attempt < policy.max_attempts && policy.is_retryable(&err) =>
+            {
+                tokio::time::sleep(policy.jittered_delay(attempt, bus.rng())).await;
+                if !live() {
+                    return (Err(err), attempt, Some(next).filter(|_| keep_last));
+                }
+
+                msg = next;
+                attempt += 1;
+            }
+            (Err(err), None) => {
+                policy.warn_not_cloneable::<M, _>(attempt, &err);
+                return (Err(err), attempt, None);
+            }
+            (Err(err), spare) => return (Err(err), attempt, spare.filter(|_| keep_last)),
+            (res, _) => return (res, attempt, None),
+        }
+    }
+}
+
+pub(crate) fn retry_blocking<M, R, E, F>(
+    policy: RetryPolicy,
+    bus: &Bus,
+    live: impl Fn() -> bool,
+    mut msg: M,
+    try_clone: fn(&M) -> Option<M>,
+    keep_last: bool,
+    mut f: F,
+) -> (Result<R, E>, u32, Option<M>)
+where
+    E: StdSyncSendError,
+    F: FnMut(M) -> Result<R, E>,
+{
+    let mut attempt = 1;
+
+    loop {
+        let spare = policy.spare(attempt, &msg, try_clone, keep_last);
+
+        match (f(msg), spare) {
+            (Err(err), Some(next))
+                if attempt < policy.max_attempts && policy.is_retryable(&err) =>
+            {
+                std::thread::sleep(policy.jittered_delay(attempt, bus.rng()));
+                if !live() {
+                    return (Err(err), attempt, Some(next).filter(|_| keep_last));
+                }
+
+                msg = next;
+                attempt += 1;
+            }
+            (Err(err), None) => {
+                policy.warn_not_cloneable::<M, _>(attempt, &err);
+                return (Err(err), attempt, None);
+            }
+            (Err(err), spare) => return (Err(err), attempt, spare.filter(|_| keep_last)),
+            (res, _) => return (res, attempt, None),
+        }
+    }
+}
+
+#[allow(clippy::ptr_arg)]
+pub(crate) fn try_clone_batch<M: crate::Message>(msgs: &Vec<M>) -> Option<Vec<M>> {
+    msgs.iter().map(crate::Message::try_clone).collect()
+}
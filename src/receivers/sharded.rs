@@ -0,0 +1,239 @@
+use core::{marker::PhantomData, mem, pin::Pin};
+use std::sync::Arc;
+
+use futures::{future, Future, FutureExt, Stream, StreamExt};
+use parking_lot::Mutex;
+
+use crate::{
+    builder::ReceiverSubscriberBuilder,
+    error::{Error, StdSyncSendError},
+    receiver::{
+        Action, BusPollerCallback, Event, ReciveTypedReceiver, SendTypedReceiver,
+        SendUntypedReceiver, UntypedPollerCallback,
+    },
+    receivers::{
+        partition::hash_key, CircuitBreakerConfig, OverflowPolicy, Partitioned, RateLimit,
+    },
+    Bus, Message, Untyped,
+};
+
+#[derive(Clone, Default)]
+pub(crate) struct ShardedConfig<C> {
+    pub shards: usize,
+    pub inner: C,
+}
+
+// N receivers of type `S`, each polling its own handler instance. A message
+// goes to the shard picked by its partition key, actions go to every shard
+// and are reported back once all the shards are done.
+pub(crate) struct Sharded<S, M> {
+    shards: Vec<S>,
+    countdowns: Arc<Countdowns>,
+    _m: PhantomData<fn(M)>,
+}
+
+impl<T, M, R, E, S> ReceiverSubscriberBuilder<T, M, R, E> for Sharded<S, M>
+where
+    T: 'static,
+    M: Partitioned,
+    R: Message,
+    E: StdSyncSendError,
+    S: ReceiverSubscriberBuilder<T, M, R, E>,
+    S::Config: Clone,
+    S::Stream: 'static,
+{
+    type Config = ShardedConfig<S::Config>;
+
+    fn circuit_breaker(cfg: &Self::Config) -> CircuitBreakerConfig {
+        S::circuit_breaker(&cfg.inner)
+    }
+
+    fn rate_limit(cfg: &Self::Config) -> RateLimit {
+        S::rate_limit(&cfg.inner)
+    }
+
+    fn overflow(cfg: &Self::Config) -> OverflowPolicy {
+        S::overflow(&cfg.inner)
+    }
+
+    fn resend_unused_resp(cfg: &Self::Config) -> bool {
+        S::resend_unused_resp(&cfg.inner)
+    }
+
+    fn build(cfg: Self::Config) -> (Self, UntypedPollerCallback) {
+        let (shards, pollers): (Vec<_>, Vec<_>) =
+            (0..cfg.shards).map(|_| S::build(cfg.inner.clone())).unzip();
+
+        // the item is the list of the handler instances, one per shard
+        let poller = Box::new(move |ut: Untyped| {
+            let items = ut.downcast::<Vec<Untyped>>().unwrap();
+            let pollers: Vec<_> = pollers
+                .into_iter()
+                .zip(items.iter())
+                .map(|(poller, item)| poller(item.clone()))
+                .collect();
+
+            Box::new(move |bus: Bus| {
+                let polling = pollers.into_iter().map(|poller| poller(bus.clone()));
+
+                Box::pin(future::join_all(polling).map(|_| ()))
+                    as Pin<Box<dyn Future<Output = ()> + Send>>
+            }) as BusPollerCallback
+        });
+
+        (
+            Sharded {
+                countdowns: Arc::new(Countdowns::new(shards.len())),
+                shards,
+                _m: Default::default(),
+            },
+            poller,
+        )
+    }
+}
+
+impl<S, M> SendUntypedReceiver for Sharded<S, M>
+where
+    S: SendUntypedReceiver,
+    M: Message,
+{
+    fn send(&self, msg: Action, bus: &Bus) -> Result<(), Error<Action>> {
+        for (sent, shard) in self.shards.iter().enumerate() {
+            if let Err(err) = shard.send(msg.clone(), bus) {
+                // the shards that did get the action still answer it, those
+                // answers must not count towards the next one
+                if let Some(countdown) = self.countdowns.of(&msg) {
+                    countdown.skip(sent);
+                }
+
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    }
+
+    // the shards size their batches independently, the mean size is reported
+    fn batch_stats(&self) -> Option<(usize, usize)> {
+        let stats: Vec<_> = self.shards.iter().filter_map(|s| s.batch_stats()).collect();
+        let capacity = stats.iter().map(|(capacity, _)| *capacity).max()?;
+        let size = stats.iter().map(|(_, size)| size).sum::<usize>() / stats.len();
+
+        Some((capacity, size))
+    }
+}
+
+impl<S, M> SendTypedReceiver<M> for Sharded<S, M>
+where
+    S: SendTypedReceiver<M>,
+    M: Partitioned,
+{
+    fn send(&self, mid: u64, msg: M, req: bool, bus: &Bus) -> Result<(), Error<M>> {
+        let shard = hash_key(&msg.partition_key()) % self.shards.len() as u64;
+
+        self.shards[shard as usize].send(mid, msg, req, bus)
+    }
+}
+
+impl<S, M, R, E> ReciveTypedReceiver<R, E> for Sharded<S, M>
+where
+    S: ReciveTypedReceiver<R, E>,
+    S::Stream: 'static,
+    M: Message,
+    R: Message,
+    E: StdSyncSendError,
+{
+    type Stream = Pin<Box<dyn Stream<Item = Event<R, E>> + Send>>;
+
+    fn event_stream(&self, bus: Bus) -> Self::Stream {
+        let events = futures::stream::select_all(
+            self.shards
+                .iter()
+                .map(|shard| shard.event_stream(bus.clone()).boxed()),
+        );
+
+        let countdowns = self.countdowns.clone();
+        let mut sync_res = Ok(());
+
+        Box::pin(events.filter_map(move |event| {
+            let event = match event {
+                Event::Ready => countdowns.ready.done().then_some(Event::Ready),
+                Event::Flushed => countdowns.flushed.done().then_some(Event::Flushed),
+                Event::Exited => countdowns.exited.done().then_some(Event::Exited),
+                Event::Synchronized(res) => {
+                    sync_res = mem::replace(&mut sync_res, Ok(())).and(res);
+
+                    countdowns
+                        .synchronized
+                        .done()
+                        .then(|| Event::Synchronized(mem::replace(&mut sync_res, Ok(()))))
+                }
+                event => Some(event),
+            };
+
+            future::ready(event)
+        }))
+    }
+}
+
+// counts the shards answering each action, shared with `send` so that an
+// action only some of the shards got is not waited on; the errors those shards
+// report on a sync are carried over to the next one
+struct Countdowns {
+    ready: Countdown,
+    flushed: Countdown,
+    exited: Countdown,
+    synchronized: Countdown,
+}
+
+impl Countdowns {
+    fn new(shards: usize) -> Self {
+        Self {
+            ready: Countdown::new(shards),
+            flushed: Countdown::new(shards),
+            exited: Countdown::new(shards),
+            synchronized: Countdown::new(shards),
+        }
+    }
+
+    fn of(&self, action: &Action) -> Option<&Countdown> {
+        match action {
+            Action::Init(_) => Some(&self.ready),
+            Action::Flush => Some(&self.flushed),
+            Action::Close => Some(&self.exited),
+            Action::Sync => Some(&self.synchronized),
+            _ => None,
+        }
+    }
+}
+
+// true once the last shard answered
+struct Countdown {
+    shards: usize,
+    left: Mutex<usize>,
+}
+
+impl Countdown {
+    fn new(shards: usize) -> Self {
+        Self {
+            shards,
+            left: Mutex::new(shards),
+        }
+    }
+
+    fn done(&self) -> bool {
+        let mut left = self.left.lock();
+        *left -= 1;
+        if *left == 0 {
+            *left = self.shards;
+            true
+        } else {
+            false
+        }
+    }
+
+    // `answers` more answers are expected before the next action counts
+    fn skip(&self, answers: usize) {
+        *self.left.lock() += answers;
+    }
+}
@@ -9,22 +9,50 @@ use crate::{
         Action, Event, ReciveTypedReceiver, SendTypedReceiver, SendUntypedReceiver,
         UntypedPollerCallback,
     },
-    receivers::Request,
+    receivers::{
+        retry_async, try_clone_batch, BatchSizer, CircuitBreakerConfig, OverflowPolicy, RateLimit,
+        Request,
+    },
     AsyncBatchSynchronizedHandler, Bus, Message, Untyped,
 };
 
 use futures::{Future, Stream};
-use tokio::sync::{
-    mpsc::{self, UnboundedSender},
-    Mutex,
+use tokio::{
+    sync::{
+        mpsc::{self, UnboundedSender},
+        Mutex,
+    },
+    time::Instant,
 };
 
 batch_synchronized_poller_macro! {
     T,
     AsyncBatchSynchronizedHandler,
-    |mids: Vec<_>, msgs, bus, ut: Arc<Mutex<T>>, stx: UnboundedSender<_>| {
+    |mids: Vec<_>, msgs, bus: Bus, ut: Arc<Mutex<T>>, stx: UnboundedSender<_>, retry, rid, sizer: Arc<BatchSizer>| {
         tokio::spawn(async move {
-            let resp = ut.lock().await.handle(msgs, &bus).await;
+            let keep = bus.wants_dead_letters::<M>();
+            let live = || bus.is_batch_live(&mids);
+            let (resp, attempts, dead) = retry_async(retry, &bus, live, msgs, try_clone_batch, keep, |msgs| {
+                let (ut, bus, sizer) = (&ut, &bus, &sizer);
+                async move {
+                    let mut ut = ut.lock().await;
+                    let (len, started) = (msgs.len(), Instant::now());
+                    let resp = ut.handle(msgs.into_iter().collect(), bus).await;
+                    if resp.is_ok() {
+                        sizer.record(len, started.elapsed());
+                    }
+
+                    resp
+                }
+            })
+            .await;
+
+            if let Err(err) = &resp {
+                bus.send_dead_letters::<T, _, _>(dead.into_iter().flatten(), err, attempts)
+                    .await;
+            }
+
+            bus.after_handle::<M, _>(rid, resp.as_ref().err()).await;
 
             crate::process_batch_result!(resp, mids, stx);
         })
@@ -39,6 +67,7 @@ where
     E: StdSyncSendError,
 {
     tx: mpsc::UnboundedSender<Request<M>>,
+    sizer: Arc<BatchSizer>,
     srx: parking_lot::Mutex<Option<mpsc::UnboundedReceiver<Event<R, E>>>>,
 }
 
@@ -52,20 +81,45 @@ where
 {
     type Config = SynchronizedBatchedConfig;
 
+    fn circuit_breaker(cfg: &Self::Config) -> CircuitBreakerConfig {
+        cfg.circuit_breaker
+    }
+
+    fn rate_limit(cfg: &Self::Config) -> RateLimit {
+        cfg.rate_limit
+    }
+
+    fn overflow(cfg: &Self::Config) -> OverflowPolicy {
+        cfg.overflow
+    }
+
+    fn resend_unused_resp(cfg: &Self::Config) -> bool {
+        cfg.resend_unused_resp
+    }
+
     fn build(cfg: Self::Config) -> (Self, UntypedPollerCallback) {
         let (stx, srx) = mpsc::unbounded_channel();
         let (tx, rx) = mpsc::unbounded_channel();
+        let sizer = Arc::new(BatchSizer::new(cfg.batch_size, cfg.adaptive));
+        let sizer_clone = sizer.clone();
 
         let poller = Box::new(move |ut| {
             Box::new(move |bus| {
-                Box::pin(batch_synchronized_poller::<T, M, R>(rx, bus, ut, cfg, stx))
-                    as Pin<Box<dyn Future<Output = ()> + Send>>
+                Box::pin(batch_synchronized_poller::<T, M, R>(
+                    rx,
+                    bus,
+                    ut,
+                    sizer_clone,
+                    cfg,
+                    stx,
+                )) as Pin<Box<dyn Future<Output = ()> + Send>>
             }) as Box<dyn FnOnce(Bus) -> Pin<Box<dyn Future<Output = ()> + Send>>>
         });
 
         (
             SynchronizedBatchedAsync::<M, R, T::Error> {
                 tx,
+                sizer,
                 srx: parking_lot::Mutex::new(Some(srx)),
             },
             poller,
@@ -86,6 +140,10 @@ where
             _ => unimplemented!(),
         }
     }
+
+    fn batch_stats(&self) -> Option<(usize, usize)> {
+        Some((self.sizer.capacity(), self.sizer.size()))
+    }
 }
 
 impl<M, R, E> SendTypedReceiver<M> for SynchronizedBatchedAsync<M, R, E>
@@ -94,10 +152,10 @@ where
     R: Message,
     E: StdSyncSendError,
 {
-    fn send(&self, mid: u64, m: M, req: bool, _bus: &Bus) -> Result<(), Error<M>> {
-        match self.tx.send(Request::Request(mid, m, req)) {
+    fn send(&self, mid: u64, m: M, req: bool, bus: &Bus) -> Result<(), Error<M>> {
+        match self.tx.send(Request::Request(mid, m, req, bus.context())) {
             Ok(_) => Ok(()),
-            Err(mpsc::error::SendError(Request::Request(_, msg, _))) => {
+            Err(mpsc::error::SendError(Request::Request(_, msg, _, _))) => {
                 Err(Error::send_closed(msg))
             }
             _ => unimplemented!(),
@@ -8,7 +8,7 @@ use crate::{
     builder::ReceiverSubscriberBuilder,
     error::{Error, StdSyncSendError},
     receiver::{Action, Event, ReciveTypedReceiver, SendTypedReceiver, SendUntypedReceiver},
-    receivers::Request,
+    receivers::{retry_async, CircuitBreakerConfig, OverflowPolicy, RateLimit, Request},
     AsyncSynchronizedHandler, Bus, Message, Untyped,
 };
 use tokio::sync::{
@@ -19,9 +19,22 @@ use tokio::sync::{
 synchronized_poller_macro! {
     T,
     AsyncSynchronizedHandler,
-    |mid, msg, bus, ut: Arc<Mutex<T>>, stx: UnboundedSender<_>| {
+    |mid, msg, bus: Bus, ut: Arc<Mutex<T>>, stx: UnboundedSender<_>, retry, rid| {
         tokio::spawn(async move {
-            let resp = ut.lock().await.handle(msg, &bus).await;
+            let keep = bus.wants_dead_letters::<M>();
+            let live = || bus.is_live();
+            let (resp, attempts, dead) = retry_async(retry, &bus, live, msg, M::try_clone, keep, |msg| {
+                let (ut, bus) = (&ut, &bus);
+                async move { ut.lock().await.handle(msg, bus).await }
+            })
+            .await;
+
+            if let Err(err) = &resp {
+                bus.send_dead_letters::<T, _, _>(dead, err, attempts)
+                    .await;
+            }
+
+            bus.after_handle::<M, _>(rid, resp.as_ref().err()).await;
 
             stx.send(Event::Response(mid, resp.map_err(Error::Other)))
                 .unwrap();
@@ -52,13 +65,29 @@ where
 {
     type Config = SynchronizedConfig;
 
-    fn build(_cfg: Self::Config) -> (Self, UntypedPollerCallback) {
+    fn circuit_breaker(cfg: &Self::Config) -> CircuitBreakerConfig {
+        cfg.circuit_breaker
+    }
+
+    fn rate_limit(cfg: &Self::Config) -> RateLimit {
+        cfg.rate_limit
+    }
+
+    fn overflow(cfg: &Self::Config) -> OverflowPolicy {
+        cfg.overflow
+    }
+
+    fn resend_unused_resp(cfg: &Self::Config) -> bool {
+        cfg.resend_unused_resp
+    }
+
+    fn build(cfg: Self::Config) -> (Self, UntypedPollerCallback) {
         let (stx, srx) = mpsc::unbounded_channel();
         let (tx, rx) = mpsc::unbounded_channel();
 
         let poller = Box::new(move |ut| {
             Box::new(move |bus| {
-                Box::pin(synchronized_poller::<T, M, R>(rx, bus, ut, stx))
+                Box::pin(synchronized_poller::<T, M, R>(rx, bus, ut, cfg, stx))
                     as Pin<Box<dyn Future<Output = ()> + Send>>
             }) as Box<dyn FnOnce(Bus) -> Pin<Box<dyn Future<Output = ()> + Send>>>
         });
@@ -94,10 +123,10 @@ where
     R: Message,
     E: StdSyncSendError,
 {
-    fn send(&self, mid: u64, m: M, req: bool, _bus: &Bus) -> Result<(), Error<M>> {
-        match self.tx.send(Request::Request(mid, m, req)) {
+    fn send(&self, mid: u64, m: M, req: bool, bus: &Bus) -> Result<(), Error<M>> {
+        match self.tx.send(Request::Request(mid, m, req, bus.context())) {
             Ok(_) => Ok(()),
-            Err(mpsc::error::SendError(Request::Request(_, msg, _))) => {
+            Err(mpsc::error::SendError(Request::Request(_, msg, _, _))) => {
                 Err(Error::send_closed(msg))
             }
             _ => unimplemented!(),
@@ -7,6 +7,8 @@ pub use r#async::SynchronizedAsync;
 use serde_derive::{Deserialize, Serialize};
 pub use sync::SynchronizedSync;
 
+use super::{CircuitBreakerConfig, OverflowPolicy, RateLimit, RetryPolicy};
+
 #[derive(Debug)]
 pub struct SynchronizedStats {
     pub buffer: AtomicU64,
@@ -16,11 +18,29 @@ pub struct SynchronizedStats {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SynchronizedConfig {
     pub buffer_size: usize,
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+    #[serde(default)]
+    pub rate_limit: RateLimit,
+    #[serde(default)]
+    pub overflow: OverflowPolicy,
+    // publish `Ok` responses nobody waits for as new messages
+    #[serde(default)]
+    pub resend_unused_resp: bool,
 }
 
 impl Default for SynchronizedConfig {
     fn default() -> Self {
-        Self { buffer_size: 1 }
+        Self {
+            buffer_size: 1,
+            retry: RetryPolicy::default(),
+            circuit_breaker: CircuitBreakerConfig::default(),
+            rate_limit: RateLimit::default(),
+            overflow: OverflowPolicy::default(),
+            resend_unused_resp: false,
+        }
     }
 }
 
@@ -31,6 +51,7 @@ macro_rules! synchronized_poller_macro {
             mut rx: mpsc::UnboundedReceiver<Request<M>>,
             bus: Bus,
             ut: Untyped,
+            cfg: SynchronizedConfig,
             stx: mpsc::UnboundedSender<Event<R, $t::Error>>,
         ) where
             $t: $h<M, Response = R> + 'static,
@@ -39,17 +60,39 @@ macro_rules! synchronized_poller_macro {
             R: Message,
         {
             let ut = ut.downcast::<Mutex<T>>().unwrap();
+            let mut receiver_id = 0;
 
             while let Some(msg) = rx.recv().await {
                 match msg {
-                    Request::Request(mid, msg, _req) =>
-                    {
+                    Request::Request(mid, mut msg, _req, ctx) => {
+                        let bus = bus.with_context(ctx);
+
+                        if let Err(err) = bus.start_message() {
+                            stx.send(Event::Response(mid, Err(err))).unwrap();
+                            continue;
+                        }
+
+                        if let Err(err) = bus.before_handle(receiver_id, &mut msg).await {
+                            stx.send(Event::Response(mid, Err(Error::OtherBoxed(err))))
+                                .unwrap();
+                            continue;
+                        }
+
                         #[allow(clippy::redundant_closure_call)]
-                        ($st1)(mid, msg, bus.clone(), ut.clone(), stx.clone())
-                            .await
-                            .unwrap()
+                        ($st1)(
+                            mid,
+                            msg,
+                            bus,
+                            ut.clone(),
+                            stx.clone(),
+                            cfg.retry,
+                            receiver_id,
+                        )
+                        .await
+                        .unwrap()
                     }
-                    Request::Action(Action::Init(..)) => {
+                    Request::Action(Action::Init(id)) => {
+                        receiver_id = id;
                         stx.send(Event::Ready).unwrap();
                     }
                     Request::Action(Action::Close) => {
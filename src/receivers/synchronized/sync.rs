@@ -8,7 +8,7 @@ use crate::{
     builder::ReceiverSubscriberBuilder,
     error::{Error, StdSyncSendError},
     receiver::{Action, Event, ReciveTypedReceiver, SendTypedReceiver, SendUntypedReceiver},
-    receivers::Request,
+    receivers::{retry_blocking, CircuitBreakerConfig, OverflowPolicy, RateLimit, Request},
     Bus, Message, SynchronizedHandler, Untyped,
 };
 use tokio::sync::{
@@ -19,9 +19,20 @@ use tokio::sync::{
 synchronized_poller_macro! {
     T,
     SynchronizedHandler,
-    |mid, msg, bus, ut: Arc<Mutex<T>>, stx: UnboundedSender<_>| {
+    |mid, msg, bus: Bus, ut: Arc<Mutex<T>>, stx: UnboundedSender<_>, retry, rid| {
         tokio::task::spawn_blocking(move || {
-            let resp = block_on(ut.lock()).handle(msg, &bus);
+            let mut ut = block_on(ut.lock());
+            let keep = bus.wants_dead_letters::<M>();
+            let live = || bus.is_live();
+            let (resp, attempts, dead) =
+                retry_blocking(retry, &bus, live, msg, M::try_clone, keep, |msg| ut.handle(msg, &bus));
+            drop(ut);
+
+            if let Err(err) = &resp {
+                block_on(bus.send_dead_letters::<T, _, _>(dead, err, attempts));
+            }
+
+            block_on(bus.after_handle::<M, _>(rid, resp.as_ref().err()));
 
             stx.send(Event::Response(mid, resp.map_err(Error::Other)))
                 .unwrap();
@@ -53,13 +64,29 @@ where
 {
     type Config = SynchronizedConfig;
 
-    fn build(_cfg: Self::Config) -> (Self, UntypedPollerCallback) {
+    fn circuit_breaker(cfg: &Self::Config) -> CircuitBreakerConfig {
+        cfg.circuit_breaker
+    }
+
+    fn rate_limit(cfg: &Self::Config) -> RateLimit {
+        cfg.rate_limit
+    }
+
+    fn overflow(cfg: &Self::Config) -> OverflowPolicy {
+        cfg.overflow
+    }
+
+    fn resend_unused_resp(cfg: &Self::Config) -> bool {
+        cfg.resend_unused_resp
+    }
+
+    fn build(cfg: Self::Config) -> (Self, UntypedPollerCallback) {
         let (stx, srx) = mpsc::unbounded_channel();
         let (tx, rx) = mpsc::unbounded_channel();
 
         let poller = Box::new(move |ut| {
             Box::new(move |bus| {
-                Box::pin(synchronized_poller::<T, M, R>(rx, bus, ut, stx))
+                Box::pin(synchronized_poller::<T, M, R>(rx, bus, ut, cfg, stx))
                     as Pin<Box<dyn Future<Output = ()> + Send>>
             }) as Box<dyn FnOnce(Bus) -> Pin<Box<dyn Future<Output = ()> + Send>>>
         });
@@ -95,10 +122,10 @@ where
     R: Message,
     E: StdSyncSendError,
 {
-    fn send(&self, mid: u64, m: M, req: bool, _bus: &Bus) -> Result<(), Error<M>> {
-        match self.tx.send(Request::Request(mid, m, req)) {
+    fn send(&self, mid: u64, m: M, req: bool, bus: &Bus) -> Result<(), Error<M>> {
+        match self.tx.send(Request::Request(mid, m, req, bus.context())) {
             Ok(_) => Ok(()),
-            Err(mpsc::error::SendError(Request::Request(_, msg, _))) => {
+            Err(mpsc::error::SendError(Request::Request(_, msg, _, _))) => {
                 Err(Error::send_closed(msg))
             }
             _ => unimplemented!(),
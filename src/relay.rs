@@ -13,6 +13,8 @@ use futures::{pin_mut, StreamExt};
 use std::sync::Arc;
 use tokio::sync::{oneshot, Notify};
 
+// A relay has no circuit breaker, rate limit or overflow policy of its own:
+// failing handlers on the remote side are for the remote bus to deal with.
 pub trait Relay: TypeTagAccept + SendUntypedReceiver + ReciveUntypedReceiver + 'static {}
 impl<T: TypeTagAccept + SendUntypedReceiver + ReciveUntypedReceiver + 'static> Relay for T {}
 
@@ -23,6 +25,10 @@ impl sharded_slab::Config for SlabCfg {
 
 type Slab<T> = sharded_slab::Slab<T, SlabCfg>;
 
+// in-flight messages per type a relay accepts, a type not sent yet has all of
+// it free
+const DEFAULT_LIMIT: u64 = 16;
+
 pub(crate) struct RelayContext {
     receivers: DashMap<TypeTag, Arc<RelayReceiverContext>>,
     need_flush: AtomicBool,
@@ -145,6 +151,10 @@ where
             .ok_or(Error::AddListenerError)? as _)
     }
 
+    fn remove_response_listener(&self, mid: u64) -> bool {
+        self.waiters.remove(mid as _)
+    }
+
     fn stats(&self) -> Stats {
         unimplemented!()
     }
@@ -193,11 +203,16 @@ where
         self.context.need_flush.store(true, Ordering::SeqCst);
     }
 
+    fn clear_need_flush(&self) {
+        self.context.need_flush.store(false, Ordering::SeqCst);
+    }
+
     fn try_reserve(&self, tt: &TypeTag) -> Option<Permit> {
         if !self.context.receivers.contains_key(tt) {
-            self.context
-                .receivers
-                .insert(tt.clone(), Arc::new(RelayReceiverContext::new(16)));
+            self.context.receivers.insert(
+                tt.clone(),
+                Arc::new(RelayReceiverContext::new(DEFAULT_LIMIT)),
+            );
         }
 
         loop {
@@ -214,6 +229,7 @@ where
                 if res.is_ok() {
                     break Some(Permit {
                         fuse: false,
+                        overflow: false,
                         inner: context.clone(),
                     });
                 }
@@ -227,9 +243,10 @@ where
 
     fn reserve_notify(&self, tt: &TypeTag) -> Arc<Notify> {
         if !self.context.receivers.contains_key(tt) {
-            self.context
-                .receivers
-                .insert(tt.clone(), Arc::new(RelayReceiverContext::new(16)));
+            self.context.receivers.insert(
+                tt.clone(),
+                Arc::new(RelayReceiverContext::new(DEFAULT_LIMIT)),
+            );
         }
 
         self.context.receivers.get(tt).unwrap().response.clone()
@@ -242,6 +259,15 @@ where
             .map(|r| r.processing.fetch_add(1, Ordering::SeqCst));
     }
 
+    fn free_capacity(&self, tt: &TypeTag) -> i64 {
+        self.context
+            .receivers
+            .get(tt)
+            .map_or(DEFAULT_LIMIT as i64, |ctx| {
+                ctx.limit as i64 - ctx.processing.load(Ordering::Relaxed) as i64
+            })
+    }
+
     fn start_polling(self: Arc<Self>) -> BusPollerCallback {
         Box::new(move |bus| {
             Box::pin(async move {
@@ -274,10 +300,7 @@ where
                             self.context.closed.notify_waiters();
                             break;
                         }
-                        Event::Flushed => {
-                            self.context.need_flush.store(false, Ordering::SeqCst);
-                            self.context.flushed.notify_one()
-                        }
+                        Event::Flushed => self.context.flushed.notify_one(),
                         Event::Synchronized(_res) => self.context.synchronized.notify_waiters(),
                         Event::Response(mid, resp) => {
                             let tt = if let Ok(bm) = &resp {
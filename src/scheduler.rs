@@ -0,0 +1,158 @@
+use std::sync::Arc;
+
+use core::{
+    sync::atomic::{AtomicU64, Ordering},
+    task::Poll,
+};
+
+use futures::future::{poll_fn, BoxFuture};
+use tokio::{
+    sync::{mpsc, Notify},
+    time::Instant,
+};
+use tokio_util::time::{delay_queue::Expired, DelayQueue};
+
+use crate::{context::Context, Bus, Message};
+
+// A message waiting for its time. It is kept apart from the code sending it,
+// so a delivery that can not be scheduled gives its message back.
+pub(crate) struct Delivery {
+    pub msg: Box<dyn Message>,
+    pub context: Option<Arc<Context>>,
+    pub send: fn(Bus, Box<dyn Message>) -> BoxFuture<'static, ()>,
+}
+
+enum Command {
+    Schedule(Instant, Delivery),
+    Close,
+}
+
+enum Wakeup {
+    Command(Option<Command>),
+    Expired(Expired<Delivery>),
+}
+
+// Delayed deliveries are kept in a timer wheel owned by a single bus task;
+// `pending` counts everything scheduled but not yet handed over to receivers
+pub(crate) struct Scheduler {
+    tx: mpsc::UnboundedSender<Command>,
+    rx: parking_lot::Mutex<Option<mpsc::UnboundedReceiver<Command>>>,
+    pending: AtomicU64,
+    drained: Notify,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        Self {
+            tx,
+            rx: parking_lot::Mutex::new(Some(rx)),
+            pending: AtomicU64::new(0),
+            drained: Notify::new(),
+        }
+    }
+
+    // the timer task is only started with the first scheduled delivery, so
+    // buses that never use delayed sends don't need it (or a runtime at build)
+    pub fn schedule(&self, bus: &Bus, at: Instant, delivery: Delivery) -> Result<(), Delivery> {
+        if let Some(rx) = self.rx.lock().take() {
+            tokio::spawn(Self::run(bus.clone(), rx));
+        }
+
+        self.pending.fetch_add(1, Ordering::SeqCst);
+
+        match self.tx.send(Command::Schedule(at, delivery)) {
+            Ok(()) => Ok(()),
+            Err(mpsc::error::SendError(cmd)) => {
+                self.done(1);
+
+                match cmd {
+                    Command::Schedule(_, delivery) => Err(delivery),
+                    Command::Close => unreachable!(),
+                }
+            }
+        }
+    }
+
+    #[inline]
+    pub fn pending(&self) -> u64 {
+        self.pending.load(Ordering::SeqCst)
+    }
+
+    pub async fn idle(&self) {
+        loop {
+            let notified = self.drained.notified();
+            futures::pin_mut!(notified);
+            notified.as_mut().enable();
+
+            if self.pending() == 0 {
+                return;
+            }
+
+            notified.await;
+        }
+    }
+
+    pub fn close(&self) {
+        let _ = self.tx.send(Command::Close);
+    }
+
+    fn done(&self, count: u64) {
+        if count > 0 && self.pending.fetch_sub(count, Ordering::SeqCst) == count {
+            self.drained.notify_waiters();
+        }
+    }
+
+    async fn run(bus: Bus, mut rx: mpsc::UnboundedReceiver<Command>) {
+        let mut queue: DelayQueue<Delivery> = DelayQueue::new();
+
+        loop {
+            let wakeup = poll_fn(|cx| {
+                if let Poll::Ready(cmd) = rx.poll_recv(cx) {
+                    return Poll::Ready(Wakeup::Command(cmd));
+                }
+
+                match queue.poll_expired(cx) {
+                    Poll::Ready(Some(expired)) => Poll::Ready(Wakeup::Expired(expired)),
+                    _ => Poll::Pending,
+                }
+            })
+            .await;
+
+            match wakeup {
+                Wakeup::Command(Some(Command::Schedule(at, delivery))) => {
+                    queue.insert_at(delivery, at);
+                }
+
+                Wakeup::Command(Some(Command::Close)) | Wakeup::Command(None) => break,
+
+                Wakeup::Expired(expired) => {
+                    let Delivery { msg, context, send } = expired.into_inner();
+                    let bus = bus.clone();
+
+                    tokio::spawn(async move {
+                        send(bus.with_context(context), msg).await;
+                        bus.inner.scheduler.done(1);
+                    });
+                }
+            }
+        }
+
+        // whatever is still waiting for its time is cancelled
+        rx.close();
+        let mut cancelled = queue.len() as u64;
+        while let Ok(cmd) = rx.try_recv() {
+            if let Command::Schedule(..) = cmd {
+                cancelled += 1;
+            }
+        }
+
+        if cancelled > 0 {
+            warn!("{} scheduled messages cancelled on close", cancelled);
+        }
+
+        drop(queue);
+        bus.inner.scheduler.done(cancelled);
+    }
+}
@@ -0,0 +1,132 @@
+use core::{
+    ops::Deref,
+    sync::atomic::{AtomicU64, Ordering},
+};
+use std::sync::Arc;
+
+use tokio::{sync::Notify, time::Instant};
+
+use crate::{context::Context, Bus};
+
+#[derive(Debug, Default)]
+pub(crate) struct ScopeState {
+    parent: Option<Arc<ScopeState>>,
+    pending: AtomicU64,
+    idle: Notify,
+}
+
+impl ScopeState {
+    pub fn start(&self) {
+        self.pending.fetch_add(1, Ordering::SeqCst);
+
+        if let Some(parent) = &self.parent {
+            parent.start();
+        }
+    }
+
+    pub fn done(&self) {
+        if self.pending.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.idle.notify_waiters();
+        }
+
+        if let Some(parent) = &self.parent {
+            parent.done();
+        }
+    }
+
+    async fn idle(&self) {
+        loop {
+            let notified = self.idle.notified();
+            futures::pin_mut!(notified);
+            notified.as_mut().enable();
+
+            if self.pending.load(Ordering::SeqCst) == 0 {
+                return;
+            }
+
+            notified.await;
+        }
+    }
+}
+
+// Handle returned by `Bus::enter`. Everything sent through it, and everything
+// its handlers send in turn, is tracked as the scope's work. Scopes entered
+// from a scoped bus are nested into the enclosing scope.
+pub struct Scope {
+    bus: Bus,
+    state: Arc<ScopeState>,
+}
+
+impl Scope {
+    pub(crate) fn new(parent: &Bus) -> Self {
+        let state = Arc::new(ScopeState {
+            parent: parent.scope_state(),
+            ..Default::default()
+        });
+
+        let context = Context::with_scope(parent.context(), state.clone());
+
+        Self {
+            bus: parent.with_context(Some(context)),
+            state,
+        }
+    }
+
+    #[inline]
+    pub fn bus(&self) -> &Bus {
+        &self.bus
+    }
+
+    // number of scope messages that are queued or being handled
+    #[inline]
+    pub fn pending(&self) -> u64 {
+        self.state.pending.load(Ordering::SeqCst)
+    }
+
+    // waits for the scope's messages to be handled, without flushing batches
+    pub async fn idle(&self) {
+        self.state.idle().await
+    }
+
+    // flushes the receivers so buffered scope messages get handled, then
+    // waits for the rest of the scope's work
+    pub async fn flush(&self) {
+        if self.pending() == 0 {
+            return;
+        }
+
+        self.bus.flush_receivers().await;
+        self.state.idle().await;
+    }
+
+    // cancels the scope: its messages that have not been picked by a handler
+    // yet are dropped, and running handlers see `Bus::is_cancelled`
+    pub fn leave(self) {
+        if let Some(context) = &self.bus.context {
+            context.cancel();
+        }
+    }
+}
+
+impl Deref for Scope {
+    type Target = Bus;
+
+    fn deref(&self) -> &Bus {
+        &self.bus
+    }
+}
+
+impl Bus {
+    pub fn enter(&self) -> Scope {
+        Scope::new(self)
+    }
+
+    pub(crate) fn scope_state(&self) -> Option<Arc<ScopeState>> {
+        self.context.as_ref().and_then(|ctx| ctx.scope())
+    }
+
+    #[inline]
+    pub(crate) fn message_context(&self, deadline: Option<Instant>) -> Option<Arc<Context>> {
+        Context::for_message(self.context(), false, deadline)
+    }
+}
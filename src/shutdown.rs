@@ -0,0 +1,183 @@
+use core::{sync::atomic::Ordering, time::Duration};
+
+use tokio::time::Instant;
+
+use crate::{receiver::Receiver, Bus};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum ShutdownMode {
+    // receivers handle everything already queued before closing
+    #[default]
+    Drain,
+    // once the producers are closed, queued messages are skipped (requests
+    // get `Error::Cancelled`), only the messages already being handled are
+    // finished
+    Drop,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct ShutdownOptions {
+    // total time given to all receivers to close
+    pub deadline: Duration,
+    pub mode: ShutdownMode,
+    // close producers first, then the stages of each pipeline in order, then
+    // the other receivers
+    pub ordered: bool,
+}
+
+impl Default for ShutdownOptions {
+    fn default() -> Self {
+        Self {
+            deadline: Duration::from_secs(20),
+            mode: ShutdownMode::Drain,
+            ordered: true,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ShutdownOutcome {
+    Closed,
+    TimedOut,
+}
+
+#[derive(Clone, Debug)]
+pub struct ReceiverShutdown {
+    pub id: u64,
+    pub name: String,
+    pub outcome: ShutdownOutcome,
+    // queued messages that were skipped, or still pending at the deadline
+    pub abandoned: u64,
+}
+
+// Receivers in the order they were closed.
+#[derive(Clone, Debug, Default)]
+pub struct ShutdownReport {
+    pub receivers: Vec<ReceiverShutdown>,
+    // messages sent by the handlers while the consumers were closing, and
+    // refused as the bus was closed
+    pub refused: u64,
+}
+
+impl ShutdownReport {
+    pub fn is_clean(&self) -> bool {
+        self.refused == 0
+            && self
+                .receivers
+                .iter()
+                .all(|r| r.outcome == ShutdownOutcome::Closed && r.abandoned == 0)
+    }
+
+    pub fn abandoned(&self) -> u64 {
+        self.receivers.iter().map(|r| r.abandoned).sum()
+    }
+}
+
+impl Bus {
+    pub async fn shutdown(&self, options: ShutdownOptions) -> ShutdownReport {
+        let _handle = self.inner.maintain.lock().await;
+        self.inner.closing_flag.store(true, Ordering::SeqCst);
+        self.inner.scheduler.close();
+        self.inner.closing.notify_waiters();
+
+        let deadline = Instant::now() + options.deadline;
+        let drain = options.mode == ShutdownMode::Drain;
+        let routes = self.inner.routes.load_full();
+
+        // pipeline stages, upstream first
+        let stages: Vec<&Vec<Receiver>> = if options.ordered {
+            self.inner.pipelines.values().flatten().collect()
+        } else {
+            Vec::new()
+        };
+
+        let (producers, consumers): (Vec<_>, Vec<_>) = routes
+            .receivers
+            .iter()
+            .filter(|r| !stages.iter().any(|s| s.contains(r)))
+            .cloned()
+            .partition(|r| options.ordered && r.is_producer());
+
+        let mut report = ShutdownReport::default();
+
+        // the producers still publish to the consumers while they are closed
+        let closing = producers
+            .iter()
+            .map(|r| self.close_receiver(r, deadline, drain));
+        report
+            .receivers
+            .extend(futures::future::join_all(closing).await);
+
+        // a stage forwards to the next one until it is closed
+        let closing = stages.iter().map(|stages| async move {
+            let mut closed = Vec::with_capacity(stages.len());
+            for r in stages.iter() {
+                closed.push(self.close_receiver(r, deadline, drain).await);
+            }
+
+            closed
+        });
+
+        report.receivers.extend(
+            futures::future::join_all(closing)
+                .await
+                .into_iter()
+                .flatten(),
+        );
+
+        let refused = self.inner.refused.load(Ordering::Relaxed);
+        self.inner.closed.store(true, Ordering::SeqCst);
+
+        if options.mode == ShutdownMode::Drop {
+            self.inner.discard.store(true, Ordering::SeqCst);
+        }
+
+        let closing = consumers
+            .iter()
+            .map(|r| self.close_receiver(r, deadline, drain));
+        report
+            .receivers
+            .extend(futures::future::join_all(closing).await);
+
+        report.refused = self.inner.refused.load(Ordering::Relaxed) - refused;
+        report
+    }
+
+    // a draining close flushes the receiver first, so partial batches are
+    // handled too
+    async fn close_receiver(
+        &self,
+        r: &Receiver,
+        deadline: Instant,
+        drain: bool,
+    ) -> ReceiverShutdown {
+        let cancelled = r.cancelled_count();
+        let closing = async {
+            if drain {
+                r.flush(self).await;
+            }
+
+            r.close(self).await
+        };
+
+        let outcome = match tokio::time::timeout_at(deadline, closing).await {
+            Ok(()) => ShutdownOutcome::Closed,
+            Err(err) => {
+                error!("Close timeout on {}: {}", r.name(), err);
+                ShutdownOutcome::TimedOut
+            }
+        };
+
+        let mut abandoned = r.cancelled_count().saturating_sub(cancelled);
+        if outcome == ShutdownOutcome::TimedOut {
+            abandoned += r.queue_size();
+        }
+
+        ReceiverShutdown {
+            id: r.id(),
+            name: r.name().to_string(),
+            outcome,
+            abandoned,
+        }
+    }
+}
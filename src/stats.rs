@@ -1,5 +1,7 @@
 use std::borrow::Cow;
 
+use crate::receivers::{CircuitState, OverflowPolicy};
+
 #[derive(Default, Debug, Clone)]
 pub struct Stats {
     pub msg_type_tag: Cow<'static, str>,
@@ -18,4 +20,20 @@ pub struct Stats {
     pub has_batch: bool,
     pub batch_capacity: i64,
     pub batch_size: i64,
+
+    pub has_circuit_breaker: bool,
+    pub circuit_state: CircuitState,
+
+    pub overflow_policy: OverflowPolicy,
+    pub overflow_dropped: i64,
+    pub cancelled_count: i64,
+    pub expired_count: i64,
+
+    pub has_dedup: bool,
+    pub dedup_size: i64,
+    pub dedup_hits: i64,
+
+    pub has_timer: bool,
+    pub timer_running: bool,
+    pub timer_ticks: i64,
 }
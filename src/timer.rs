@@ -0,0 +1,102 @@
+use core::{
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    time::Duration,
+};
+use std::sync::Arc;
+
+use chrono::Utc;
+use futures::future::{select, Either};
+use tokio::time::Instant;
+
+use crate::{receiver::BusPollerCallback, stats::Stats, Bus, Message};
+
+pub(crate) enum Period {
+    Interval(Duration),
+    Cron(Box<cron::Schedule>),
+}
+
+impl Period {
+    fn next(&self, prev: Instant) -> Option<Instant> {
+        let now = Instant::now();
+
+        match self {
+            // missed ticks are skipped instead of being fired in a burst
+            Period::Interval(period) => Some((prev + *period).max(now)),
+            Period::Cron(schedule) => {
+                let next = schedule.upcoming(Utc).next()?;
+                let delay = (next - Utc::now()).to_std().unwrap_or_default();
+
+                Some(now + delay)
+            }
+        }
+    }
+}
+
+// Periodic message source registered with `BusBuilder::register_interval`
+// or `BusBuilder::register_cron`
+pub(crate) struct Timer {
+    msg_type_tag: crate::TypeTag,
+    period: Period,
+    ticks: AtomicU64,
+    running: AtomicBool,
+}
+
+impl Timer {
+    pub fn new<M: Message>(period: Period) -> Self {
+        Self {
+            msg_type_tag: M::type_tag_(),
+            period,
+            ticks: AtomicU64::new(0),
+            running: AtomicBool::new(true),
+        }
+    }
+
+    pub fn stats(&self) -> Stats {
+        Stats {
+            msg_type_tag: self.msg_type_tag.clone(),
+            has_timer: true,
+            timer_running: self.running.load(Ordering::SeqCst),
+            timer_ticks: self.ticks.load(Ordering::SeqCst) as _,
+            ..Default::default()
+        }
+    }
+
+    pub fn start<M, F>(self: Arc<Self>, mut factory: F) -> BusPollerCallback
+    where
+        M: Message + Clone,
+        F: FnMut() -> M + Send + 'static,
+    {
+        Box::new(move |bus: Bus| {
+            Box::pin(async move {
+                let mut at = match self.period {
+                    Period::Interval(period) => Some(Instant::now() + period),
+                    Period::Cron(_) => self.period.next(Instant::now()),
+                };
+
+                while let Some(deadline) = at {
+                    let closing = bus.inner.closing.notified();
+                    let sleep = tokio::time::sleep_until(deadline);
+                    futures::pin_mut!(closing, sleep);
+                    closing.as_mut().enable();
+
+                    if bus.is_closing() {
+                        break;
+                    }
+
+                    if let Either::Right(_) = select(sleep, closing).await {
+                        break;
+                    }
+
+                    self.ticks.fetch_add(1, Ordering::SeqCst);
+                    if let Err(err) = bus.send(factory()).await {
+                        error!("Timer send error: {}", err);
+                    }
+
+                    at = self.period.next(deadline);
+                }
+
+                self.running.store(false, Ordering::SeqCst);
+            })
+        })
+    }
+}
@@ -0,0 +1,191 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use messagebus::{
+    derive::{Error as MbError, Message},
+    error,
+    receivers::{AdaptiveBatch, BufferUnorderedBatchedConfig, SynchronizedBatchedConfig},
+    AsyncBatchHandler, BatchSynchronizedHandler, Bus, Message, TypeTagged,
+};
+use parking_lot::Mutex;
+use thiserror::Error;
+
+#[derive(Debug, Error, Clone, MbError)]
+enum Error {
+    #[error("Error({0})")]
+    Error(Arc<anyhow::Error>),
+}
+
+impl<M: Message> From<error::Error<M>> for Error {
+    fn from(err: error::Error<M>) -> Self {
+        Self::Error(Arc::new(err.into()))
+    }
+}
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Row(u32);
+
+struct Writer {
+    // time taken for each batch and for each of its rows
+    overhead: Duration,
+    per_row: Duration,
+    sizes: Arc<Mutex<Vec<usize>>>,
+}
+
+#[async_trait]
+impl AsyncBatchHandler<Row> for Writer {
+    type Error = Error;
+    type Response = ();
+    type InBatch = Vec<Row>;
+    type OutBatch = Vec<()>;
+
+    async fn handle(&self, msg: Vec<Row>, _bus: &Bus) -> Result<Vec<Self::Response>, Self::Error> {
+        self.sizes.lock().push(msg.len());
+        tokio::time::sleep(self.overhead + self.per_row * msg.len() as u32).await;
+
+        Ok(vec![(); msg.len()])
+    }
+}
+
+impl BatchSynchronizedHandler<Row> for Writer {
+    type Error = Error;
+    type Response = ();
+    type InBatch = Vec<Row>;
+    type OutBatch = Vec<()>;
+
+    fn handle(&mut self, msg: Vec<Row>, _bus: &Bus) -> Result<Vec<Self::Response>, Self::Error> {
+        self.sizes.lock().push(msg.len());
+        std::thread::sleep(self.overhead + self.per_row * msg.len() as u32);
+
+        Ok(vec![(); msg.len()])
+    }
+}
+
+// capacity and current size of the batches
+fn batch_size(b: &Bus) -> (i64, i64) {
+    let stats = b
+        .stats()
+        .find(|s| s.msg_type_tag == Row::type_tag_())
+        .unwrap();
+
+    assert!(stats.has_batch);
+    (stats.batch_capacity, stats.batch_size)
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_adaptive_latency_grow() {
+    let sizes = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, poller) = Bus::build()
+        .register(Writer {
+            overhead: Duration::ZERO,
+            per_row: Duration::from_millis(1),
+            sizes: sizes.clone(),
+        })
+        .subscribe_batch_async::<Row>(
+            1024,
+            BufferUnorderedBatchedConfig {
+                buffer_size: 1024,
+                max_parallel: 1,
+                batch_size: 2,
+                adaptive: AdaptiveBatch::latency(Duration::from_millis(20), 1, 64),
+                ..Default::default()
+            },
+        )
+        .done()
+        .build();
+
+    assert_eq!(batch_size(&b), (64, 2));
+
+    for i in 0..400 {
+        b.send(Row(i)).await.unwrap();
+    }
+    b.flush_all().await;
+
+    // about 20 rows of 1ms fit in the target
+    let (_, size) = batch_size(&b);
+    assert!((10..=40).contains(&size), "batch size {}", size);
+    assert!(sizes.lock().iter().any(|len| *len >= 10));
+    assert_eq!(sizes.lock().iter().sum::<usize>(), 400);
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_adaptive_latency_shrink() {
+    let sizes = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, poller) = Bus::build()
+        .register_unsync(Writer {
+            overhead: Duration::ZERO,
+            per_row: Duration::from_millis(5),
+            sizes: sizes.clone(),
+        })
+        .subscribe_batch_sync::<Row>(
+            64,
+            SynchronizedBatchedConfig {
+                buffer_size: 64,
+                batch_size: 16,
+                adaptive: AdaptiveBatch::latency(Duration::from_millis(10), 2, 32),
+                ..Default::default()
+            },
+        )
+        .done()
+        .build();
+
+    // the batches of a round are cut at once, the size adapts between rounds
+    for round in 0..4 {
+        for i in 0..16 {
+            b.send(Row(round * 16 + i)).await.unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(150)).await;
+    }
+
+    // never below `min_size`
+    assert_eq!(batch_size(&b), (32, 2));
+    assert_eq!(sizes.lock()[0], 16);
+    assert_eq!(sizes.lock().iter().sum::<usize>(), 64);
+
+    b.close().await;
+    poller.await;
+}
+
+// the clock only moves with the handler's sleeps, so every batch of the same
+// size takes exactly as long
+#[tokio::test(start_paused = true)]
+async fn test_adaptive_throughput() {
+    let sizes = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, poller) = Bus::build()
+        .register(Writer {
+            overhead: Duration::from_millis(2),
+            per_row: Duration::from_micros(20),
+            sizes: sizes.clone(),
+        })
+        .subscribe_batch_async::<Row>(
+            1024,
+            BufferUnorderedBatchedConfig {
+                buffer_size: 1024,
+                max_parallel: 1,
+                batch_size: 4,
+                adaptive: AdaptiveBatch::throughput(1, 64),
+                ..Default::default()
+            },
+        )
+        .done()
+        .build();
+
+    for i in 0..600 {
+        b.send(Row(i)).await.unwrap();
+    }
+    b.flush_all().await;
+
+    // the per batch overhead makes bigger batches pay off
+    let (_, size) = batch_size(&b);
+    assert!(size > 16, "batch size {}", size);
+
+    b.close().await;
+    poller.await;
+}
@@ -42,6 +42,7 @@ async fn test_backpressure() {
             receivers::BufferUnorderedConfig {
                 buffer_size: 1,
                 max_parallel: 1,
+                ..Default::default()
             },
         )
         .done()
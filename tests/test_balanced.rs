@@ -0,0 +1,112 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use async_trait::async_trait;
+use messagebus::{
+    derive::{Error as MbError, Message},
+    error, receivers, AsyncHandler, Bus, Message, SendOptions,
+};
+use thiserror::Error;
+
+#[derive(Debug, Error, MbError)]
+enum Error {
+    #[error("Error({0})")]
+    Error(anyhow::Error),
+}
+
+impl<M: Message> From<error::Error<M>> for Error {
+    fn from(err: error::Error<M>) -> Self {
+        Self::Error(err.into())
+    }
+}
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct MsgU32(pub u32);
+
+struct Worker {
+    counter: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl AsyncHandler<MsgU32> for Worker {
+    type Error = Error;
+    type Response = ();
+
+    async fn handle(&self, _msg: MsgU32, _bus: &Bus) -> Result<Self::Response, Self::Error> {
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        self.counter.fetch_add(1, Ordering::SeqCst);
+
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_balanced() {
+    let counter1 = Arc::new(AtomicUsize::new(0));
+    let counter2 = Arc::new(AtomicUsize::new(0));
+
+    let (b, poller) = Bus::build()
+        .register(Worker {
+            counter: counter1.clone(),
+        })
+        .subscribe_async::<MsgU32>(4, receivers::BufferUnorderedConfig::default())
+        .done()
+        .register(Worker {
+            counter: counter2.clone(),
+        })
+        .subscribe_async::<MsgU32>(4, receivers::BufferUnorderedConfig::default())
+        .done()
+        .build();
+
+    for i in 0..32 {
+        b.send_ext(MsgU32(i), SendOptions::Balanced).await.unwrap();
+    }
+
+    b.flush_all().await;
+
+    let (c1, c2) = (
+        counter1.load(Ordering::SeqCst),
+        counter2.load(Ordering::SeqCst),
+    );
+
+    assert_eq!(c1 + c2, 32);
+    assert!(c1 > 0);
+    assert!(c2 > 0);
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_balanced_try_send() {
+    let counter1 = Arc::new(AtomicUsize::new(0));
+    let counter2 = Arc::new(AtomicUsize::new(0));
+
+    let (b, poller) = Bus::build()
+        .register(Worker {
+            counter: counter1.clone(),
+        })
+        .subscribe_async::<MsgU32>(1, receivers::BufferUnorderedConfig::default())
+        .done()
+        .register(Worker {
+            counter: counter2.clone(),
+        })
+        .subscribe_async::<MsgU32>(1, receivers::BufferUnorderedConfig::default())
+        .done()
+        .build();
+
+    b.try_send_ext(MsgU32(1), SendOptions::Balanced).unwrap();
+    b.try_send_ext(MsgU32(2), SendOptions::Balanced).unwrap();
+    assert!(b.try_send_ext(MsgU32(3), SendOptions::Balanced).is_err());
+
+    b.flush_all().await;
+
+    assert_eq!(counter1.load(Ordering::SeqCst), 1);
+    assert_eq!(counter2.load(Ordering::SeqCst), 1);
+
+    b.close().await;
+    poller.await;
+}
@@ -0,0 +1,254 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use messagebus::{
+    derive::{Error as MbError, Message},
+    error,
+    receivers::{BufferUnorderedConfig, CircuitBreakerConfig, CircuitState},
+    AsyncHandler, Bus, Message,
+};
+use thiserror::Error;
+
+#[derive(Debug, Error, MbError)]
+enum Error {
+    #[error("Boom")]
+    Boom,
+
+    #[error("Error({0})")]
+    Error(anyhow::Error),
+}
+
+impl<M: Message> From<error::Error<M>> for Error {
+    fn from(err: error::Error<M>) -> Self {
+        Self::Error(err.into())
+    }
+}
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Msg(u32);
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Resp(u32);
+
+struct TmpReceiver {
+    failing: Arc<AtomicBool>,
+    handled: Arc<AtomicU32>,
+}
+
+#[async_trait]
+impl AsyncHandler<Msg> for TmpReceiver {
+    type Error = Error;
+    type Response = Resp;
+
+    async fn handle(&self, msg: Msg, _bus: &Bus) -> Result<Self::Response, Self::Error> {
+        self.handled.fetch_add(1, Ordering::SeqCst);
+
+        if self.failing.load(Ordering::SeqCst) {
+            Err(Error::Boom)
+        } else {
+            Ok(Resp(msg.0))
+        }
+    }
+}
+
+fn circuit_state(b: &Bus) -> CircuitState {
+    b.stats()
+        .find(|s| s.has_circuit_breaker)
+        .unwrap()
+        .circuit_state
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_circuit_breaker() {
+    let failing = Arc::new(AtomicBool::new(true));
+    let handled = Arc::new(AtomicU32::new(0));
+
+    let (b, poller) = Bus::build()
+        .register(TmpReceiver {
+            failing: failing.clone(),
+            handled: handled.clone(),
+        })
+        .subscribe_async::<Msg>(
+            8,
+            BufferUnorderedConfig {
+                circuit_breaker: CircuitBreakerConfig::new(3).cooldown(Duration::from_millis(50)),
+                ..Default::default()
+            },
+        )
+        .done()
+        .build();
+
+    for i in 0..3 {
+        b.send(Msg(i)).await.unwrap();
+    }
+
+    b.flush_all().await;
+    assert_eq!(handled.load(Ordering::SeqCst), 3);
+    assert_eq!(circuit_state(&b), CircuitState::Open);
+
+    assert!(matches!(
+        b.send(Msg(3)).await,
+        Err(error::Error::CircuitOpen)
+    ));
+    assert!(matches!(b.try_send(Msg(4)), Err(error::Error::CircuitOpen)));
+    assert!(matches!(
+        b.request::<_, Resp>(Msg(5), Default::default()).await,
+        Err(error::Error::CircuitOpen)
+    ));
+
+    b.flush_all().await;
+    assert_eq!(handled.load(Ordering::SeqCst), 3);
+
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    failing.store(false, Ordering::SeqCst);
+
+    let resp = b
+        .request::<_, Resp>(Msg(6), Default::default())
+        .await
+        .unwrap();
+    assert_eq!(resp.0, 6);
+    assert_eq!(circuit_state(&b), CircuitState::Closed);
+
+    b.send(Msg(7)).await.unwrap();
+    b.flush_all().await;
+    assert_eq!(handled.load(Ordering::SeqCst), 5);
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_circuit_breaker_trial_fails() {
+    let handled = Arc::new(AtomicU32::new(0));
+
+    let (b, poller) = Bus::build()
+        .register(TmpReceiver {
+            failing: Arc::new(AtomicBool::new(true)),
+            handled: handled.clone(),
+        })
+        .subscribe_async::<Msg>(
+            8,
+            BufferUnorderedConfig {
+                circuit_breaker: CircuitBreakerConfig::new(2).cooldown(Duration::from_millis(50)),
+                ..Default::default()
+            },
+        )
+        .done()
+        .build();
+
+    b.send(Msg(0)).await.unwrap();
+    b.send(Msg(1)).await.unwrap();
+    b.flush_all().await;
+    assert_eq!(circuit_state(&b), CircuitState::Open);
+
+    tokio::time::sleep(Duration::from_millis(60)).await;
+
+    // the trial goes through, everything else is rejected until it is done
+    b.send(Msg(2)).await.unwrap();
+    assert!(matches!(
+        b.send(Msg(3)).await,
+        Err(error::Error::CircuitOpen)
+    ));
+
+    b.flush_all().await;
+    assert_eq!(handled.load(Ordering::SeqCst), 3);
+    assert_eq!(circuit_state(&b), CircuitState::Open);
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_circuit_breaker_window() {
+    let handled = Arc::new(AtomicU32::new(0));
+
+    let (b, poller) = Bus::build()
+        .register(TmpReceiver {
+            failing: Arc::new(AtomicBool::new(true)),
+            handled: handled.clone(),
+        })
+        .subscribe_async::<Msg>(
+            8,
+            BufferUnorderedConfig {
+                circuit_breaker: CircuitBreakerConfig::new(2).window(Duration::from_millis(20)),
+                ..Default::default()
+            },
+        )
+        .done()
+        .build();
+
+    b.send(Msg(0)).await.unwrap();
+    b.flush_all().await;
+
+    tokio::time::sleep(Duration::from_millis(30)).await;
+
+    b.send(Msg(1)).await.unwrap();
+    b.flush_all().await;
+    assert_eq!(circuit_state(&b), CircuitState::Closed);
+
+    b.send(Msg(2)).await.unwrap();
+    b.flush_all().await;
+    assert_eq!(handled.load(Ordering::SeqCst), 3);
+    assert_eq!(circuit_state(&b), CircuitState::Open);
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_circuit_breaker_trial_expired() {
+    let failing = Arc::new(AtomicBool::new(true));
+    let handled = Arc::new(AtomicU32::new(0));
+
+    let (b, poller) = Bus::build()
+        .register(TmpReceiver {
+            failing: failing.clone(),
+            handled: handled.clone(),
+        })
+        .subscribe_async::<Msg>(
+            8,
+            BufferUnorderedConfig {
+                circuit_breaker: CircuitBreakerConfig::new(2).cooldown(Duration::from_millis(50)),
+                ..Default::default()
+            },
+        )
+        .done()
+        .build();
+
+    b.send(Msg(0)).await.unwrap();
+    b.send(Msg(1)).await.unwrap();
+    b.flush_all().await;
+    assert_eq!(circuit_state(&b), CircuitState::Open);
+
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    failing.store(false, Ordering::SeqCst);
+
+    // the trial expires before it is handled, so it has no outcome
+    b.send_with_ttl(Msg(2), Duration::ZERO).await.unwrap();
+    b.flush_all().await;
+    assert_eq!(handled.load(Ordering::SeqCst), 2);
+    assert_eq!(circuit_state(&b), CircuitState::HalfOpen);
+    assert!(matches!(
+        b.send(Msg(3)).await,
+        Err(error::Error::CircuitOpen)
+    ));
+
+    // given up on after another cool-down
+    tokio::time::sleep(Duration::from_millis(60)).await;
+
+    b.send(Msg(4)).await.unwrap();
+    b.flush_all().await;
+    assert_eq!(handled.load(Ordering::SeqCst), 3);
+    assert_eq!(circuit_state(&b), CircuitState::Closed);
+
+    b.close().await;
+    poller.await;
+}
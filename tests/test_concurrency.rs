@@ -92,6 +92,7 @@ fn module() -> Module {
             BufferUnorderedConfig {
                 buffer_size: 1024,
                 max_parallel: 1024,
+                ..Default::default()
             },
         )
         .subscribe_async::<Resp>(1024, Default::default())
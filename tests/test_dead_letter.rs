@@ -0,0 +1,247 @@
+use std::{
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use messagebus::{
+    derive::{Error as MbError, Message},
+    error::{self, StdSyncSendError},
+    receivers::{BufferUnorderedConfig, RetryPolicy},
+    AsyncHandler, BatchHandler, Bus, DeadLetter, Handler, Message, Middleware, TypeTag, TypeTagged,
+};
+use parking_lot::Mutex;
+use thiserror::Error;
+
+#[derive(Debug, Error, Clone, MbError)]
+enum Error {
+    #[error("Boom({0})")]
+    Boom(u32),
+
+    #[error("Error({0})")]
+    Error(Arc<anyhow::Error>),
+}
+
+impl<M: Message> From<error::Error<M>> for Error {
+    fn from(err: error::Error<M>) -> Self {
+        Self::Error(Arc::new(err.into()))
+    }
+}
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Msg(u32);
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct BatchMsg(u32);
+
+struct FailingReceiver;
+
+#[async_trait]
+impl AsyncHandler<Msg> for FailingReceiver {
+    type Error = Error;
+    type Response = ();
+
+    async fn handle(&self, msg: Msg, _bus: &Bus) -> Result<Self::Response, Self::Error> {
+        if msg.0 % 2 == 0 {
+            Ok(())
+        } else {
+            Err(Error::Boom(msg.0))
+        }
+    }
+}
+
+impl BatchHandler<BatchMsg> for FailingReceiver {
+    type Error = Error;
+    type Response = ();
+    type InBatch = Vec<BatchMsg>;
+    type OutBatch = Vec<()>;
+
+    fn handle(&self, msgs: Vec<BatchMsg>, _bus: &Bus) -> Result<Vec<()>, Self::Error> {
+        Err(Error::Boom(msgs.len() as _))
+    }
+}
+
+struct DeadLetterReceiver {
+    letters: Arc<Mutex<Vec<(u32, String, &'static str, u32)>>>,
+}
+
+impl Handler<DeadLetter<Msg>> for DeadLetterReceiver {
+    type Error = Error;
+    type Response = ();
+
+    fn handle(&self, dl: DeadLetter<Msg>, _bus: &Bus) -> Result<Self::Response, Self::Error> {
+        self.letters
+            .lock()
+            .push((dl.message.0, dl.error.description, dl.receiver, dl.attempts));
+
+        Ok(())
+    }
+}
+
+impl Handler<DeadLetter<BatchMsg>> for DeadLetterReceiver {
+    type Error = Error;
+    type Response = ();
+
+    fn handle(&self, dl: DeadLetter<BatchMsg>, _bus: &Bus) -> Result<Self::Response, Self::Error> {
+        self.letters
+            .lock()
+            .push((dl.message.0, dl.error.description, dl.receiver, dl.attempts));
+
+        Ok(())
+    }
+}
+
+// counts the dead letters going through the send path
+struct CountDeadLetters(Arc<AtomicU32>);
+
+#[async_trait]
+impl Middleware for CountDeadLetters {
+    async fn on_send(
+        &self,
+        tt: &TypeTag,
+        _msg: &mut dyn Message,
+        _bus: &Bus,
+    ) -> Result<(), Box<dyn StdSyncSendError>> {
+        if *tt == DeadLetter::<Msg>::type_tag_() {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_dead_letter() {
+    let letters = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, poller) = Bus::build()
+        .register(FailingReceiver)
+        .subscribe_async::<Msg>(
+            8,
+            BufferUnorderedConfig {
+                retry: RetryPolicy::new(2)
+                    .backoff(Duration::from_millis(1), Duration::from_millis(1)),
+                ..Default::default()
+            },
+        )
+        .done()
+        .register(DeadLetterReceiver {
+            letters: letters.clone(),
+        })
+        .subscribe_sync::<DeadLetter<Msg>>(8, Default::default())
+        .done()
+        .build();
+
+    for i in 0..4 {
+        b.send(Msg(i)).await.unwrap();
+    }
+
+    b.flush_all().await;
+
+    let mut letters = letters.lock().clone();
+    letters.sort_by_key(|x| x.0);
+
+    assert_eq!(letters.len(), 2);
+    assert_eq!(letters[0].0, 1);
+    assert_eq!(letters[1].0, 3);
+
+    for (_, err, receiver, attempts) in letters {
+        assert!(err.contains("Boom"));
+        assert!(receiver.ends_with("FailingReceiver"));
+        assert_eq!(attempts, 2);
+    }
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_dead_letter_batch() {
+    let letters = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, poller) = Bus::build()
+        .register(FailingReceiver)
+        .subscribe_batch_sync::<BatchMsg>(8, Default::default())
+        .done()
+        .register(DeadLetterReceiver {
+            letters: letters.clone(),
+        })
+        .subscribe_sync::<DeadLetter<BatchMsg>>(8, Default::default())
+        .done()
+        .build();
+
+    for i in 0..3 {
+        b.send(BatchMsg(i)).await.unwrap();
+    }
+
+    b.flush_all().await;
+
+    let mut letters = letters.lock().clone();
+    letters.sort_by_key(|x| x.0);
+
+    assert_eq!(
+        letters.iter().map(|x| x.0).collect::<Vec<_>>(),
+        vec![0, 1, 2]
+    );
+    assert!(letters.iter().all(|x| x.3 == 1));
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_no_dead_letter_receivers() {
+    let (b, poller) = Bus::build()
+        .register(FailingReceiver)
+        .subscribe_async::<Msg>(8, Default::default())
+        .done()
+        .build();
+
+    b.send(Msg(1)).await.unwrap();
+
+    b.flush_all().await;
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_dead_letter_send_path() {
+    let letters = Arc::new(Mutex::new(Vec::new()));
+    let sent = Arc::new(AtomicU32::new(0));
+
+    let (b, poller) = Bus::build()
+        .middleware(CountDeadLetters(sent.clone()))
+        .register(FailingReceiver)
+        .subscribe_async::<Msg>(
+            8,
+            BufferUnorderedConfig {
+                max_parallel: 1,
+                ..Default::default()
+            },
+        )
+        .done()
+        .register(DeadLetterReceiver {
+            letters: letters.clone(),
+        })
+        .subscribe_sync::<DeadLetter<Msg>>(1, Default::default())
+        .done()
+        .build();
+
+    for i in 0..8 {
+        b.send(Msg(i)).await.unwrap();
+    }
+
+    // the flush waits for the dead letters still being sent
+    b.flush_all().await;
+
+    assert_eq!(letters.lock().len(), 4);
+    assert_eq!(sent.load(Ordering::Relaxed), 4);
+
+    b.close().await;
+    poller.await;
+}
@@ -0,0 +1,153 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use messagebus::{
+    derive::{Error as MbError, Message},
+    error, AsyncHandler, Bus, DedupWindow, Deduplicate, Message,
+};
+use parking_lot::Mutex;
+use thiserror::Error;
+
+#[derive(Debug, Error, MbError)]
+enum Error {
+    #[error("Error({0})")]
+    Error(anyhow::Error),
+}
+
+impl<M: Message> From<error::Error<M>> for Error {
+    fn from(err: error::Error<M>) -> Self {
+        Self::Error(err.into())
+    }
+}
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Msg {
+    id: u32,
+    value: u32,
+}
+
+impl Deduplicate for Msg {
+    type Key = u32;
+
+    fn idempotency_key(&self) -> u32 {
+        self.id
+    }
+}
+
+struct TmpReceiver {
+    received: Arc<Mutex<Vec<u32>>>,
+}
+
+#[async_trait]
+impl AsyncHandler<Msg> for TmpReceiver {
+    type Error = Error;
+    type Response = ();
+
+    async fn handle(&self, msg: Msg, _bus: &Bus) -> Result<Self::Response, Self::Error> {
+        self.received.lock().push(msg.value);
+        Ok(())
+    }
+}
+
+fn dedup_hits(b: &Bus) -> i64 {
+    b.stats().find(|s| s.has_dedup).unwrap().dedup_hits
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_dedup_time_window() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, poller) = Bus::build()
+        .deduplicate::<Msg>(DedupWindow::Time(Duration::from_millis(50)))
+        .register(TmpReceiver {
+            received: received.clone(),
+        })
+        .subscribe_async::<Msg>(8, Default::default())
+        .done()
+        .build();
+
+    b.send(Msg { id: 1, value: 1 }).await.unwrap();
+    b.send(Msg { id: 1, value: 2 }).await.unwrap();
+    b.try_send(Msg { id: 2, value: 3 }).unwrap();
+    b.try_send(Msg { id: 2, value: 4 }).unwrap();
+    b.send_boxed(Box::new(Msg { id: 1, value: 5 }), Default::default())
+        .await
+        .unwrap();
+
+    b.flush_all().await;
+    assert_eq!(dedup_hits(&b), 3);
+
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    b.send(Msg { id: 1, value: 6 }).await.unwrap();
+    b.flush_all().await;
+
+    let mut values = received.lock().clone();
+    values.sort_unstable();
+    assert_eq!(values, vec![1, 3, 6]);
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_dedup_size_window() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, poller) = Bus::build()
+        .deduplicate::<Msg>(DedupWindow::Size(2))
+        .register(TmpReceiver {
+            received: received.clone(),
+        })
+        .subscribe_async::<Msg>(8, Default::default())
+        .done()
+        .build();
+
+    for (id, value) in [(1, 1), (2, 2), (1, 3), (3, 4), (1, 5), (3, 6)] {
+        b.send(Msg { id, value }).await.unwrap();
+    }
+
+    b.flush_all().await;
+
+    let mut values = received.lock().clone();
+    values.sort_unstable();
+    assert_eq!(values, vec![1, 2, 4, 5]);
+
+    let stats = b.stats().find(|s| s.has_dedup).unwrap();
+    assert_eq!(stats.dedup_hits, 2);
+    assert_eq!(stats.dedup_size, 2);
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_dedup_failed_send() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, poller) = Bus::build()
+        .deduplicate::<Msg>(DedupWindow::Size(8))
+        .register(TmpReceiver {
+            received: received.clone(),
+        })
+        .subscribe_async::<Msg>(1, Default::default())
+        .done()
+        .build();
+
+    b.try_send(Msg { id: 1, value: 1 }).unwrap();
+    assert!(matches!(
+        b.try_send(Msg { id: 2, value: 2 }),
+        Err(error::Error::SendError(error::SendError::Full(_)))
+    ));
+
+    // the rejected message was not taken as seen
+    b.flush_all().await;
+    b.try_send(Msg { id: 2, value: 3 }).unwrap();
+    b.flush_all().await;
+
+    assert_eq!(received.lock().clone(), vec![1, 3]);
+    assert_eq!(dedup_hits(&b), 0);
+
+    b.close().await;
+    poller.await;
+}
@@ -0,0 +1,254 @@
+use std::{pin::Pin, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use futures::Stream;
+use messagebus::{
+    derive::{Error as MbError, Message},
+    error::{self, GenericError},
+    receivers::BufferUnorderedConfig,
+    Action, AsyncHandler, Bus, Event, Message, ReciveUntypedReceiver, SendOptions,
+    SendUntypedReceiver, TypeTag, TypeTagAccept, TypeTagAcceptItem, TypeTagged,
+};
+use parking_lot::Mutex;
+use thiserror::Error;
+use tokio::sync::mpsc;
+
+#[derive(Debug, Error, MbError)]
+enum Error {
+    #[error("Error({0})")]
+    Error(anyhow::Error),
+}
+
+impl<M: Message> From<error::Error<M>> for Error {
+    fn from(err: error::Error<M>) -> Self {
+        Self::Error(err.into())
+    }
+}
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Job(u32);
+
+type Log = Arc<Mutex<Vec<(&'static str, u32)>>>;
+
+struct Worker {
+    name: &'static str,
+    log: Log,
+}
+
+#[async_trait]
+impl AsyncHandler<Job> for Worker {
+    type Error = Error;
+    type Response = ();
+
+    async fn handle(&self, msg: Job, _bus: &Bus) -> Result<Self::Response, Self::Error> {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        self.log.lock().push((self.name, msg.0));
+        Ok(())
+    }
+}
+
+// stands for the members of a group living in another process
+struct RemoteMembers {
+    log: Log,
+    stx: mpsc::UnboundedSender<Event<Box<dyn Message>, GenericError>>,
+    srx: Mutex<Option<mpsc::UnboundedReceiver<Event<Box<dyn Message>, GenericError>>>>,
+}
+
+impl TypeTagAccept for RemoteMembers {
+    fn iter_types(&self) -> Box<dyn Iterator<Item = TypeTagAcceptItem>> {
+        Box::new(std::iter::once((Job::type_tag_(), None)))
+    }
+
+    fn accept_msg(&self, msg: &TypeTag) -> bool {
+        msg.as_ref() == Job::type_tag_().as_ref()
+    }
+
+    fn accept_req(&self, _req: &TypeTag, _resp: Option<&TypeTag>, _err: Option<&TypeTag>) -> bool {
+        false
+    }
+}
+
+impl SendUntypedReceiver for RemoteMembers {
+    fn send(&self, msg: Action, _bus: &Bus) -> Result<(), error::Error<Action>> {
+        match msg {
+            Action::Init(..) => self.stx.send(Event::Ready).unwrap(),
+            Action::Close => self.stx.send(Event::Exited).unwrap(),
+            Action::Flush => self.stx.send(Event::Flushed).unwrap(),
+            Action::Sync => self.stx.send(Event::Synchronized(Ok(()))).unwrap(),
+            _ => unimplemented!(),
+        }
+
+        Ok(())
+    }
+
+    fn send_msg(
+        &self,
+        mid: u64,
+        msg: Box<dyn Message>,
+        _req: bool,
+        _bus: &Bus,
+    ) -> Result<(), error::Error<Box<dyn Message>>> {
+        let job = msg.as_any_ref().downcast_ref::<Job>().unwrap();
+        self.log.lock().push(("remote", job.0));
+        self.stx
+            .send(Event::Response(mid, Ok(Box::new(()))))
+            .unwrap();
+
+        Ok(())
+    }
+}
+
+impl ReciveUntypedReceiver for RemoteMembers {
+    type Stream = Pin<Box<dyn Stream<Item = Event<Box<dyn Message>, GenericError>> + Send>>;
+
+    fn event_stream(&self, _: Bus) -> Self::Stream {
+        let mut rx = self.srx.lock().take().unwrap();
+
+        Box::pin(futures::stream::poll_fn(move |cx| rx.poll_recv(cx)))
+    }
+}
+
+fn cfg() -> BufferUnorderedConfig {
+    BufferUnorderedConfig {
+        max_parallel: 1,
+        ..Default::default()
+    }
+}
+
+fn handled_by(log: &Log, names: &[&str]) -> Vec<u32> {
+    let mut jobs: Vec<_> = log
+        .lock()
+        .iter()
+        .filter(|(name, _)| names.contains(name))
+        .map(|(_, job)| *job)
+        .collect();
+
+    jobs.sort_unstable();
+    jobs
+}
+
+#[tokio::test]
+async fn test_group_broadcast() {
+    let log = Log::default();
+    let worker = |name| Worker {
+        name,
+        log: log.clone(),
+    };
+
+    let (b, poller) = Bus::build()
+        .register(worker("worker-1"))
+        .group("workers")
+        .subscribe_async::<Job>(4, cfg())
+        .done()
+        .register(worker("worker-2"))
+        .group("workers")
+        .subscribe_async::<Job>(4, cfg())
+        .done()
+        .register(worker("billing"))
+        .group("billing")
+        .subscribe_async::<Job>(4, cfg())
+        .done()
+        .register(worker("audit"))
+        .subscribe_async::<Job>(4, cfg())
+        .done()
+        .build();
+
+    for i in 0..20 {
+        b.send(Job(i)).await.unwrap();
+    }
+    b.flush_all().await;
+
+    let all: Vec<_> = (0..20).collect();
+    assert_eq!(handled_by(&log, &["audit"]), all);
+    assert_eq!(handled_by(&log, &["billing"]), all);
+
+    // each job reaches one of the workers, the load is shared
+    assert_eq!(handled_by(&log, &["worker-1", "worker-2"]), all);
+    assert!(!handled_by(&log, &["worker-1"]).is_empty());
+    assert!(!handled_by(&log, &["worker-2"]).is_empty());
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_group_send() {
+    let log = Log::default();
+    let worker = |name| Worker {
+        name,
+        log: log.clone(),
+    };
+
+    let (b, poller) = Bus::build()
+        .register(worker("worker-1"))
+        .group("workers")
+        .subscribe_async::<Job>(4, cfg())
+        .done()
+        .register(worker("worker-2"))
+        .group("workers")
+        .subscribe_async::<Job>(4, cfg())
+        .done()
+        .register(worker("billing"))
+        .group("billing")
+        .subscribe_async::<Job>(4, cfg())
+        .done()
+        .register(worker("audit"))
+        .subscribe_async::<Job>(4, cfg())
+        .done()
+        .build();
+
+    for i in 0..4 {
+        b.send_ext(Job(i), SendOptions::Group("workers"))
+            .await
+            .unwrap();
+    }
+    b.flush_all().await;
+
+    assert_eq!(
+        handled_by(&log, &["worker-1", "worker-2"]),
+        vec![0, 1, 2, 3]
+    );
+    assert!(handled_by(&log, &["billing", "audit"]).is_empty());
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_group_relay() {
+    let log = Log::default();
+    let (stx, srx) = mpsc::unbounded_channel();
+
+    let (b, poller) = Bus::build()
+        .register_group_relay(
+            RemoteMembers {
+                log: log.clone(),
+                stx,
+                srx: Mutex::new(Some(srx)),
+            },
+            "workers",
+        )
+        .register(Worker {
+            name: "worker-1",
+            log: log.clone(),
+        })
+        .group("workers")
+        .subscribe_async::<Job>(4, cfg())
+        .done()
+        .build();
+
+    for i in 0..10 {
+        b.send(Job(i)).await.unwrap();
+    }
+    b.flush_all().await;
+
+    assert_eq!(
+        handled_by(&log, &["worker-1", "remote"]),
+        (0..10).collect::<Vec<_>>()
+    );
+    assert!(!handled_by(&log, &["remote"]).is_empty());
+
+    b.close().await;
+    poller.await;
+}
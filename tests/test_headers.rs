@@ -0,0 +1,129 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use messagebus::{
+    derive::{Error as MbError, Message},
+    error, receivers, AsyncHandler, Bus, Headers, Message, SendOptions,
+};
+use parking_lot::Mutex;
+use thiserror::Error;
+
+#[derive(Debug, Error, MbError)]
+enum Error {
+    #[error("Error({0})")]
+    Error(anyhow::Error),
+}
+
+impl<M: Message> From<error::Error<M>> for Error {
+    fn from(err: error::Error<M>) -> Self {
+        Self::Error(err.into())
+    }
+}
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Outer;
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Inner;
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct WhoAmI;
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Tenant(pub Option<String>);
+
+struct Recorder {
+    seen: Arc<Mutex<Vec<(&'static str, Option<String>)>>>,
+}
+
+fn tenant(bus: &Bus) -> Option<String> {
+    bus.headers()
+        .and_then(|h| h.get("tenant"))
+        .map(ToString::to_string)
+}
+
+#[async_trait]
+impl AsyncHandler<Outer> for Recorder {
+    type Error = Error;
+    type Response = ();
+
+    async fn handle(&self, _msg: Outer, bus: &Bus) -> Result<Self::Response, Self::Error> {
+        self.seen.lock().push(("outer", tenant(bus)));
+        bus.send(Inner).await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AsyncHandler<Inner> for Recorder {
+    type Error = Error;
+    type Response = ();
+
+    async fn handle(&self, _msg: Inner, bus: &Bus) -> Result<Self::Response, Self::Error> {
+        self.seen.lock().push(("inner", tenant(bus)));
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AsyncHandler<WhoAmI> for Recorder {
+    type Error = Error;
+    type Response = Tenant;
+
+    async fn handle(&self, _msg: WhoAmI, bus: &Bus) -> Result<Self::Response, Self::Error> {
+        Ok(Tenant(tenant(bus)))
+    }
+}
+
+#[tokio::test]
+async fn test_headers_propagation() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, poller) = Bus::build()
+        .register(Recorder { seen: seen.clone() })
+        .subscribe_async::<Outer>(8, receivers::BufferUnorderedConfig::default())
+        .subscribe_async::<Inner>(8, receivers::BufferUnorderedConfig::default())
+        .subscribe_async::<WhoAmI>(8, receivers::BufferUnorderedConfig::default())
+        .done()
+        .build();
+
+    b.send_with_headers(Outer, Headers::new().with("tenant", "acme"))
+        .await
+        .unwrap();
+
+    b.flush_all().await;
+
+    let mut seen_list = seen.lock().clone();
+    seen_list.sort();
+    assert_eq!(
+        seen_list,
+        vec![
+            ("inner", Some("acme".to_string())),
+            ("outer", Some("acme".to_string())),
+        ]
+    );
+
+    let Tenant(t) = b
+        .with_headers(Headers::new().with("tenant", "globex"))
+        .request::<_, Tenant>(WhoAmI, SendOptions::Broadcast)
+        .await
+        .unwrap();
+
+    assert_eq!(t.as_deref(), Some("globex"));
+
+    let Tenant(t) = b
+        .request::<_, Tenant>(WhoAmI, SendOptions::Broadcast)
+        .await
+        .unwrap();
+
+    assert_eq!(t, None);
+
+    b.close().await;
+    poller.await;
+}
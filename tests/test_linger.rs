@@ -0,0 +1,177 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use messagebus::{
+    derive::{Error as MbError, Message},
+    error,
+    receivers::{BufferUnorderedBatchedConfig, SynchronizedBatchedConfig},
+    AsyncBatchHandler, BatchSynchronizedHandler, Bus, Message, TypeTagged,
+};
+use parking_lot::Mutex;
+use thiserror::Error;
+
+#[derive(Debug, Error, Clone, MbError)]
+enum Error {
+    #[error("Error({0})")]
+    Error(Arc<anyhow::Error>),
+}
+
+impl<M: Message> From<error::Error<M>> for Error {
+    fn from(err: error::Error<M>) -> Self {
+        Self::Error(Arc::new(err.into()))
+    }
+}
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Row(u32);
+
+type Batches = Arc<Mutex<Vec<Vec<u32>>>>;
+
+struct Writer {
+    batches: Batches,
+}
+
+#[async_trait]
+impl AsyncBatchHandler<Row> for Writer {
+    type Error = Error;
+    type Response = ();
+    type InBatch = Vec<Row>;
+    type OutBatch = Vec<()>;
+
+    async fn handle(&self, msg: Vec<Row>, _bus: &Bus) -> Result<Vec<Self::Response>, Self::Error> {
+        self.batches
+            .lock()
+            .push(msg.into_iter().map(|x| x.0).collect());
+
+        Ok(vec![])
+    }
+}
+
+impl BatchSynchronizedHandler<Row> for Writer {
+    type Error = Error;
+    type Response = ();
+    type InBatch = Vec<Row>;
+    type OutBatch = Vec<()>;
+
+    fn handle(&mut self, msg: Vec<Row>, _bus: &Bus) -> Result<Vec<Self::Response>, Self::Error> {
+        self.batches
+            .lock()
+            .push(msg.into_iter().map(|x| x.0).collect());
+
+        Ok(vec![])
+    }
+}
+
+#[tokio::test]
+async fn test_linger_async() {
+    let batches = Batches::default();
+
+    let (b, poller) = Bus::build()
+        .register(Writer {
+            batches: batches.clone(),
+        })
+        .subscribe_batch_async::<Row>(
+            16,
+            BufferUnorderedBatchedConfig {
+                batch_size: 8,
+                max_linger: Duration::from_millis(50),
+                ..Default::default()
+            },
+        )
+        .done()
+        .build();
+
+    b.send(Row(1)).await.unwrap();
+    b.send(Row(2)).await.unwrap();
+
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(batches.lock().is_empty());
+
+    // dispatched without a flush
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(*batches.lock(), vec![vec![1, 2]]);
+
+    // a flush does not wait for the deadline
+    b.send(Row(3)).await.unwrap();
+    b.flush_all().await;
+    assert_eq!(*batches.lock(), vec![vec![1, 2], vec![3]]);
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_linger_min_batch_size() {
+    let batches = Batches::default();
+
+    let (b, poller) = Bus::build()
+        .register_unsync(Writer {
+            batches: batches.clone(),
+        })
+        .subscribe_batch_sync::<Row>(
+            16,
+            SynchronizedBatchedConfig {
+                batch_size: 8,
+                max_linger: Duration::from_millis(30),
+                min_batch_size: 3,
+                ..Default::default()
+            },
+        )
+        .done()
+        .build();
+
+    b.send(Row(1)).await.unwrap();
+    b.send(Row(2)).await.unwrap();
+
+    // too small to be dispatched on linger
+    tokio::time::sleep(Duration::from_millis(80)).await;
+    assert!(batches.lock().is_empty());
+
+    // the deadline has passed already, the third row completes the batch
+    b.send(Row(3)).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert_eq!(*batches.lock(), vec![vec![1, 2, 3]]);
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_linger_expired() {
+    let batches = Batches::default();
+
+    let (b, poller) = Bus::build()
+        .register(Writer {
+            batches: batches.clone(),
+        })
+        .subscribe_batch_async::<Row>(
+            16,
+            BufferUnorderedBatchedConfig {
+                batch_size: 8,
+                max_linger: Duration::from_millis(50),
+                ..Default::default()
+            },
+        )
+        .done()
+        .build();
+
+    // expires while it waits for the batch to be dispatched
+    b.send_with_ttl(Row(1), Duration::from_millis(20))
+        .await
+        .unwrap();
+    b.send(Row(2)).await.unwrap();
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(*batches.lock(), vec![vec![2]]);
+
+    let expired = b
+        .stats()
+        .find(|s| s.msg_type_tag == Row::type_tag_())
+        .unwrap()
+        .expired_count;
+    assert_eq!(expired, 1);
+
+    b.close().await;
+    poller.await;
+}
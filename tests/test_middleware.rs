@@ -0,0 +1,176 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use messagebus::{
+    derive::{Error as MbError, Message},
+    error::{self, StdSyncSendError},
+    AsyncHandler, Bus, Message, Middleware, TypeTag, TypeTagged,
+};
+use parking_lot::Mutex;
+use thiserror::Error;
+
+#[derive(Debug, Error, MbError)]
+enum Error {
+    #[error("Rejected({0})")]
+    Rejected(i32),
+
+    #[error("Error({0})")]
+    Error(anyhow::Error),
+}
+
+impl<M: Message> From<error::Error<M>> for Error {
+    fn from(err: error::Error<M>) -> Self {
+        Self::Error(err.into())
+    }
+}
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Msg(i32);
+
+struct TmpReceiver {
+    handled: Arc<Mutex<Vec<i32>>>,
+}
+
+#[async_trait]
+impl AsyncHandler<Msg> for TmpReceiver {
+    type Error = Error;
+    type Response = ();
+
+    async fn handle(&self, msg: Msg, _bus: &Bus) -> Result<Self::Response, Self::Error> {
+        self.handled.lock().push(msg.0);
+
+        if msg.0 == 21 {
+            Err(Error::Rejected(msg.0))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+struct Audit {
+    handled: Arc<Mutex<Vec<(u64, TypeTag, bool)>>>,
+}
+
+#[async_trait]
+impl Middleware for Audit {
+    async fn on_send(
+        &self,
+        _tt: &TypeTag,
+        msg: &mut dyn Message,
+        _bus: &Bus,
+    ) -> Result<(), Box<dyn StdSyncSendError>> {
+        match msg.as_any_ref().downcast_ref::<Msg>() {
+            Some(Msg(x)) if *x < 0 => Err(Box::new(Error::Rejected(*x))),
+            _ => Ok(()),
+        }
+    }
+
+    async fn before_handle(
+        &self,
+        _receiver_id: u64,
+        _tt: &TypeTag,
+        msg: &mut dyn Message,
+        _bus: &Bus,
+    ) -> Result<(), Box<dyn StdSyncSendError>> {
+        if let Some(msg) = msg.as_any_mut().downcast_mut::<Msg>() {
+            if msg.0 != 21 {
+                msg.0 *= 10;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn after_handle(
+        &self,
+        receiver_id: u64,
+        tt: &TypeTag,
+        err: Option<&dyn StdSyncSendError>,
+        _bus: &Bus,
+    ) {
+        self.handled
+            .lock()
+            .push((receiver_id, tt.clone(), err.is_none()));
+    }
+}
+
+struct Delay;
+
+#[async_trait]
+impl Middleware for Delay {
+    async fn on_send(
+        &self,
+        _tt: &TypeTag,
+        _msg: &mut dyn Message,
+        _bus: &Bus,
+    ) -> Result<(), Box<dyn StdSyncSendError>> {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_middleware() {
+    let audit = Arc::new(Mutex::new(Vec::new()));
+    let handled = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, poller) = Bus::build()
+        .middleware(Audit {
+            handled: audit.clone(),
+        })
+        .register(TmpReceiver {
+            handled: handled.clone(),
+        })
+        .subscribe_async::<Msg>(8, Default::default())
+        .done()
+        .build();
+
+    b.send(Msg(1)).await.unwrap();
+    b.send(Msg(2)).await.unwrap();
+    b.send(Msg(21)).await.unwrap();
+
+    assert!(matches!(
+        b.send(Msg(-1)).await,
+        Err(error::Error::OtherBoxed(_))
+    ));
+
+    b.flush_all().await;
+
+    let mut values = handled.lock().clone();
+    values.sort_unstable();
+    assert_eq!(values, vec![10, 20, 21]);
+
+    let audit = audit.lock();
+    assert_eq!(audit.len(), 3);
+    assert!(audit.iter().all(|(id, _, _)| *id != 0));
+    assert!(audit.iter().all(|(_, tt, _)| *tt == Msg::type_tag_()));
+    assert_eq!(audit.iter().filter(|(_, _, ok)| !ok).count(), 1);
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_middleware_delay() {
+    let handled = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, poller) = Bus::build()
+        .middleware(Delay)
+        .register(TmpReceiver {
+            handled: handled.clone(),
+        })
+        .subscribe_async::<Msg>(8, Default::default())
+        .done()
+        .build();
+
+    assert!(matches!(b.try_send(Msg(1)), Err(error::Error::Other(_))));
+
+    b.send(Msg(2)).await.unwrap();
+    b.flush_all().await;
+
+    assert_eq!(handled.lock().clone(), vec![2]);
+
+    b.close().await;
+    poller.await;
+}
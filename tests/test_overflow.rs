@@ -0,0 +1,206 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use messagebus::{
+    derive::{Error as MbError, Message},
+    error::{self, SendError},
+    receivers::{BufferUnorderedConfig, OverflowPolicy},
+    AsyncHandler, Bus, Message, Middleware, TypeTag, TypeTagged,
+};
+use parking_lot::Mutex;
+use thiserror::Error;
+
+#[derive(Debug, Error, MbError)]
+enum Error {
+    #[error("Error({0})")]
+    Error(anyhow::Error),
+}
+
+impl<M: Message> From<error::Error<M>> for Error {
+    fn from(err: error::Error<M>) -> Self {
+        Self::Error(err.into())
+    }
+}
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Msg(u32);
+
+struct TmpReceiver {
+    received: Arc<Mutex<Vec<u32>>>,
+}
+
+#[async_trait]
+impl AsyncHandler<Msg> for TmpReceiver {
+    type Error = Error;
+    type Response = ();
+
+    async fn handle(&self, msg: Msg, _bus: &Bus) -> Result<Self::Response, Self::Error> {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        self.received.lock().push(msg.0);
+        Ok(())
+    }
+}
+
+struct DropObserver {
+    dropped: Arc<AtomicU64>,
+}
+
+impl Middleware for DropObserver {
+    fn on_drop(&self, _receiver_id: u64, tt: &TypeTag, policy: OverflowPolicy, _bus: &Bus) {
+        assert_eq!(tt, &Msg::type_tag_());
+        assert_ne!(policy, OverflowPolicy::Block);
+        self.dropped.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+fn overflow_dropped(b: &Bus) -> i64 {
+    b.stats()
+        .find(|s| s.msg_type_tag == Msg::type_tag_())
+        .unwrap()
+        .overflow_dropped
+}
+
+// the first message is being handled, the second one waits in the queue
+// and the queue is full
+async fn fill(b: &Bus) {
+    b.send(Msg(0)).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    b.send(Msg(1)).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_overflow_reject() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let dropped = Arc::new(AtomicU64::new(0));
+
+    let (b, poller) = Bus::build()
+        .middleware(DropObserver {
+            dropped: dropped.clone(),
+        })
+        .register(TmpReceiver {
+            received: received.clone(),
+        })
+        .subscribe_async::<Msg>(
+            2,
+            BufferUnorderedConfig {
+                max_parallel: 1,
+                overflow: OverflowPolicy::Reject,
+                ..Default::default()
+            },
+        )
+        .done()
+        .build();
+    fill(&b).await;
+
+    let res = tokio::time::timeout(Duration::from_millis(20), b.send(Msg(2)))
+        .await
+        .unwrap();
+    assert!(matches!(
+        res,
+        Err(error::Error::SendError(SendError::Full(Msg(2))))
+    ));
+    assert!(matches!(
+        b.force_send(Msg(3)),
+        Err(error::Error::SendError(SendError::Full(Msg(3))))
+    ));
+
+    b.flush_all().await;
+    assert_eq!(received.lock().clone(), vec![0, 1]);
+    assert_eq!(dropped.load(Ordering::SeqCst), 0);
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_overflow_drop_newest() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let dropped = Arc::new(AtomicU64::new(0));
+
+    let (b, poller) = Bus::build()
+        .middleware(DropObserver {
+            dropped: dropped.clone(),
+        })
+        .register(TmpReceiver {
+            received: received.clone(),
+        })
+        .subscribe_async::<Msg>(
+            2,
+            BufferUnorderedConfig {
+                max_parallel: 1,
+                overflow: OverflowPolicy::DropNewest,
+                ..Default::default()
+            },
+        )
+        .done()
+        .build();
+    fill(&b).await;
+
+    b.send(Msg(2)).await.unwrap();
+    b.try_send(Msg(3)).unwrap();
+    b.force_send(Msg(4)).unwrap();
+
+    b.flush_all().await;
+    assert_eq!(received.lock().clone(), vec![0, 1]);
+    assert_eq!(dropped.load(Ordering::SeqCst), 3);
+    assert_eq!(overflow_dropped(&b), 3);
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_overflow_drop_oldest() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let dropped = Arc::new(AtomicU64::new(0));
+
+    let (b, poller) = Bus::build()
+        .middleware(DropObserver {
+            dropped: dropped.clone(),
+        })
+        .register(TmpReceiver {
+            received: received.clone(),
+        })
+        .subscribe_async::<Msg>(
+            2,
+            BufferUnorderedConfig {
+                max_parallel: 1,
+                overflow: OverflowPolicy::DropOldest,
+                ..Default::default()
+            },
+        )
+        .done()
+        .build();
+    fill(&b).await;
+
+    b.send(Msg(2)).await.unwrap();
+    b.try_send(Msg(3)).unwrap();
+
+    b.flush_all().await;
+    assert_eq!(received.lock().clone(), vec![0, 3]);
+    assert_eq!(dropped.load(Ordering::SeqCst), 2);
+    assert_eq!(overflow_dropped(&b), 2);
+
+    // an evicted message is a drop, not a cancellation as well
+    let stats = b
+        .stats()
+        .find(|s| s.msg_type_tag == Msg::type_tag_())
+        .unwrap();
+    assert_eq!(stats.cancelled_count, 0);
+
+    // the queue is empty again: nothing is dropped
+    b.send(Msg(4)).await.unwrap();
+    b.flush_all().await;
+    assert_eq!(received.lock().clone(), vec![0, 3, 4]);
+    assert_eq!(overflow_dropped(&b), 2);
+
+    b.close().await;
+    poller.await;
+}
@@ -0,0 +1,103 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use messagebus::{
+    derive::{Error as MbError, Message},
+    error,
+    receivers::{partition_by, BufferUnorderedConfig, Partitioned},
+    AsyncHandler, Bus, Message,
+};
+use parking_lot::Mutex;
+use thiserror::Error;
+
+#[derive(Debug, Error, MbError)]
+enum Error {
+    #[error("Error({0})")]
+    Error(anyhow::Error),
+}
+
+impl<M: Message> From<error::Error<M>> for Error {
+    fn from(err: error::Error<M>) -> Self {
+        Self::Error(err.into())
+    }
+}
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Update {
+    account: u32,
+    seq: u32,
+}
+
+impl Partitioned for Update {
+    type Key = u32;
+
+    fn partition_key(&self) -> u32 {
+        self.account
+    }
+}
+
+struct Accounts {
+    applied: Arc<Mutex<Vec<(u32, u32)>>>,
+}
+
+#[async_trait]
+impl AsyncHandler<Update> for Accounts {
+    type Error = Error;
+    type Response = ();
+
+    async fn handle(&self, msg: Update, _bus: &Bus) -> Result<Self::Response, Self::Error> {
+        // later updates are faster, so they would overtake the earlier ones
+        tokio::time::sleep(Duration::from_millis(5 * (5 - msg.seq as u64))).await;
+        self.applied.lock().push((msg.account, msg.seq));
+        Ok(())
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_partition_key_order() {
+    let applied = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, poller) = Bus::build()
+        .register(Accounts {
+            applied: applied.clone(),
+        })
+        .subscribe_async::<Update>(
+            32,
+            BufferUnorderedConfig {
+                max_parallel: 8,
+                partition_key: Some(partition_by::<Update>()),
+                ..Default::default()
+            },
+        )
+        .done()
+        .build();
+
+    let start = tokio::time::Instant::now();
+    for seq in 0..5 {
+        for account in 0..4 {
+            b.send(Update { account, seq }).await.unwrap();
+        }
+    }
+
+    b.flush_all().await;
+
+    // 75ms per account, 300ms in all: the accounts were handled in parallel
+    assert!(start.elapsed() < Duration::from_millis(150));
+
+    let applied = applied.lock().clone();
+    assert_eq!(applied.len(), 20);
+
+    for account in 0..4 {
+        let seqs: Vec<_> = applied
+            .iter()
+            .filter(|(a, _)| *a == account)
+            .map(|(_, seq)| *seq)
+            .collect();
+
+        assert_eq!(seqs, vec![0, 1, 2, 3, 4]);
+    }
+
+    b.close().await;
+    poller.await;
+}
@@ -0,0 +1,314 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use messagebus::{
+    derive::{Error as MbError, Message},
+    error,
+    receivers::{BufferUnorderedBatchedConfig, BufferUnorderedConfig},
+    AsyncBatchHandler, AsyncHandler, AsyncSynchronizedHandler, Bus, Message,
+};
+use parking_lot::Mutex;
+use thiserror::Error;
+
+#[derive(Debug, Error, Clone, MbError)]
+enum Error {
+    #[error("Error({0})")]
+    Error(Arc<anyhow::Error>),
+}
+
+impl<M: Message> From<error::Error<M>> for Error {
+    fn from(err: error::Error<M>) -> Self {
+        Self::Error(Arc::new(err.into()))
+    }
+}
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Raw(String);
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Parsed(u32);
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Doubled(u32);
+
+struct Parser;
+
+#[async_trait]
+impl AsyncHandler<Raw> for Parser {
+    type Error = Error;
+    type Response = Parsed;
+
+    async fn handle(&self, msg: Raw, _bus: &Bus) -> Result<Self::Response, Self::Error> {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        Ok(Parsed(msg.0.parse().map_err(|err| {
+            Error::Error(Arc::new(anyhow::Error::from(err)))
+        })?))
+    }
+}
+
+struct Doubler;
+
+#[async_trait]
+impl AsyncBatchHandler<Parsed> for Doubler {
+    type Error = Error;
+    type Response = Doubled;
+    type InBatch = Vec<Parsed>;
+    type OutBatch = Vec<Doubled>;
+
+    async fn handle(&self, msg: Vec<Parsed>, _bus: &Bus) -> Result<Vec<Doubled>, Self::Error> {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        Ok(msg.into_iter().map(|x| Doubled(x.0 * 2)).collect())
+    }
+}
+
+struct Collector {
+    received: Arc<Mutex<Vec<u32>>>,
+}
+
+#[async_trait]
+impl AsyncHandler<Doubled> for Collector {
+    type Error = Error;
+    type Response = ();
+
+    async fn handle(&self, msg: Doubled, _bus: &Bus) -> Result<Self::Response, Self::Error> {
+        self.received.lock().push(msg.0);
+        Ok(())
+    }
+}
+
+// a subscriber of `Parsed` outside of the pipeline
+struct Observer {
+    received: Arc<Mutex<Vec<u32>>>,
+}
+
+#[async_trait]
+impl AsyncHandler<Parsed> for Observer {
+    type Error = Error;
+    type Response = ();
+
+    async fn handle(&self, msg: Parsed, _bus: &Bus) -> Result<Self::Response, Self::Error> {
+        self.received.lock().push(msg.0);
+        Ok(())
+    }
+}
+
+// keeps a running total, so it needs `&mut self`
+struct Totals {
+    total: u32,
+    received: Arc<Mutex<Vec<u32>>>,
+}
+
+#[async_trait]
+impl AsyncSynchronizedHandler<Parsed> for Totals {
+    type Error = Error;
+    type Response = ();
+
+    async fn handle(&mut self, msg: Parsed, _bus: &Bus) -> Result<Self::Response, Self::Error> {
+        self.total += msg.0;
+        self.received.lock().push(self.total);
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_pipeline_idle() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let observed = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, poller) = Bus::build()
+        .pipeline::<Raw>()
+        .stage(Parser, 8, BufferUnorderedConfig::default())
+        .stage_batch(
+            Doubler,
+            8,
+            BufferUnorderedBatchedConfig {
+                batch_size: 4,
+                ..Default::default()
+            },
+        )
+        .stage(
+            Collector {
+                received: received.clone(),
+            },
+            8,
+            BufferUnorderedConfig {
+                max_parallel: 1,
+                ..Default::default()
+            },
+        )
+        .done()
+        .register(Observer {
+            received: observed.clone(),
+        })
+        .subscribe_async::<Parsed>(8, Default::default())
+        .done()
+        .build();
+
+    for i in 1..=6 {
+        b.send(Raw(i.to_string())).await.unwrap();
+    }
+    b.send(Raw("nan".into())).await.unwrap();
+
+    b.idle_pipeline::<Raw>().await;
+
+    let mut values = received.lock().clone();
+    values.sort_unstable();
+    assert_eq!(values, vec![2, 4, 6, 8, 10, 12]);
+
+    // stage responses go to the next stage only
+    assert!(observed.lock().is_empty());
+
+    // messages sent by others still reach every subscriber
+    b.send(Parsed(7)).await.unwrap();
+    b.flush_all().await;
+    assert_eq!(observed.lock().clone(), vec![7]);
+    assert!(received.lock().contains(&14));
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_pipeline_same_input() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let totals = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, poller) = Bus::build()
+        .pipeline::<Raw>()
+        .stage(Parser, 8, BufferUnorderedConfig::default())
+        .stage_batch(Doubler, 8, BufferUnorderedBatchedConfig::default())
+        .stage(
+            Collector {
+                received: received.clone(),
+            },
+            8,
+            BufferUnorderedConfig::default(),
+        )
+        .done()
+        .pipeline::<Raw>()
+        .stage(Parser, 8, BufferUnorderedConfig::default())
+        .stage_synchronized(
+            Totals {
+                total: 0,
+                received: totals.clone(),
+            },
+            8,
+            Default::default(),
+        )
+        .done()
+        .build();
+
+    for i in 1..=4 {
+        b.send(Raw(i.to_string())).await.unwrap();
+    }
+
+    // both pipelines get every message, each passing it along its own stages
+    b.idle_pipeline::<Raw>().await;
+
+    let mut values = received.lock().clone();
+    values.sort_unstable();
+    assert_eq!(values, vec![2, 4, 6, 8]);
+    assert_eq!(totals.lock().len(), 4);
+    assert_eq!(totals.lock().last(), Some(&10));
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_pipeline_flush() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let observed = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, poller) = Bus::build()
+        .pipeline::<Raw>()
+        .stage(Parser, 8, BufferUnorderedConfig::default())
+        .stage_batch(
+            Doubler,
+            8,
+            BufferUnorderedBatchedConfig {
+                batch_size: 4,
+                ..Default::default()
+            },
+        )
+        .stage(
+            Collector {
+                received: received.clone(),
+            },
+            8,
+            BufferUnorderedConfig {
+                max_parallel: 1,
+                ..Default::default()
+            },
+        )
+        .done()
+        .register(Observer {
+            received: observed.clone(),
+        })
+        .subscribe_async::<Parsed>(8, Default::default())
+        .done()
+        .build();
+
+    // a partial batch on the second stage is flushed too
+    b.send(Raw("1".into())).await.unwrap();
+    b.flush_pipeline::<Raw>().await;
+    assert_eq!(received.lock().clone(), vec![2]);
+
+    // nothing to do for a type not starting a pipeline
+    b.flush_pipeline::<Parsed>().await;
+    b.idle_pipeline::<Parsed>().await;
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_pipeline_shutdown() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, poller) = Bus::build()
+        .pipeline::<Raw>()
+        .stage(Parser, 8, BufferUnorderedConfig::default())
+        .stage_batch(
+            Doubler,
+            8,
+            BufferUnorderedBatchedConfig {
+                batch_size: 4,
+                ..Default::default()
+            },
+        )
+        .stage(
+            Collector {
+                received: received.clone(),
+            },
+            8,
+            BufferUnorderedConfig::default(),
+        )
+        .done()
+        .build();
+
+    for i in 1..=6 {
+        b.send(Raw(i.to_string())).await.unwrap();
+    }
+
+    // every stage is drained before the next one is closed
+    let report = b.shutdown(Default::default()).await;
+    assert!(report.is_clean());
+
+    let mut values = received.lock().clone();
+    values.sort_unstable();
+    assert_eq!(values, vec![2, 4, 6, 8, 10, 12]);
+
+    let names = report
+        .receivers
+        .iter()
+        .map(|r| r.name.as_str())
+        .collect::<Vec<_>>();
+    assert!(names[0].contains("BufferUnorderedAsync"));
+    assert!(names[1].contains("BufferUnorderedBatchedAsync"));
+
+    poller.await;
+}
@@ -0,0 +1,114 @@
+use std::{
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+};
+
+use async_trait::async_trait;
+use futures::Stream;
+use messagebus::{
+    derive::{Error as MbError, Message},
+    error, receivers, AsyncHandler, AsyncProducer, Bus, Message, ProducerStats, SendOptions,
+};
+use thiserror::Error;
+
+#[derive(Debug, Error, MbError)]
+enum Error {
+    #[error("Error({0})")]
+    Error(anyhow::Error),
+}
+
+impl<M: Message> From<error::Error<M>> for Error {
+    fn from(err: error::Error<M>) -> Self {
+        Self::Error(err.into())
+    }
+}
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Range(pub u32);
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Item(pub u32);
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Produced(pub usize);
+
+struct Counter;
+
+#[async_trait]
+impl AsyncProducer<Range> for Counter {
+    type Item = Item;
+    type Response = Produced;
+    type Error = Error;
+
+    async fn producer(
+        &self,
+        msg: Range,
+        _bus: &Bus,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<Self::Item, Self::Error>> + Send + '_>>, Self::Error>
+    {
+        Ok(Box::pin(futures::stream::iter(
+            (0..msg.0).map(|x| Ok(Item(x))),
+        )))
+    }
+
+    async fn finish(
+        &self,
+        stats: ProducerStats,
+        _bus: &Bus,
+    ) -> Result<Self::Response, Self::Error> {
+        Ok(Produced(stats.completed))
+    }
+}
+
+struct Sum {
+    total: Arc<AtomicU32>,
+}
+
+#[async_trait]
+impl AsyncHandler<Item> for Sum {
+    type Error = Error;
+    type Response = ();
+
+    async fn handle(&self, msg: Item, _bus: &Bus) -> Result<Self::Response, Self::Error> {
+        self.total.fetch_add(msg.0, Ordering::SeqCst);
+
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_producer() {
+    let total = Arc::new(AtomicU32::new(0));
+
+    let (b, poller) = Bus::build()
+        .register(Counter)
+        .subscribe_producer::<Range>(8, receivers::AsyncProducerConfig::default())
+        .done()
+        .register(Sum {
+            total: total.clone(),
+        })
+        .subscribe_async::<Item>(8, receivers::BufferUnorderedConfig::default())
+        .done()
+        .build();
+
+    let Produced(count) = b
+        .request::<_, Produced>(Range(10), SendOptions::Broadcast)
+        .await
+        .unwrap();
+
+    assert_eq!(count, 10);
+
+    b.send(Range(5)).await.unwrap();
+    b.flush_all().await;
+
+    assert_eq!(total.load(Ordering::SeqCst), 45 + 10);
+
+    b.close().await;
+    poller.await;
+}
@@ -0,0 +1,166 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use async_trait::async_trait;
+use messagebus::{
+    derive::{Error as MbError, Message},
+    error, receivers, AsyncHandler, Bus, Message, SendOptions,
+};
+use parking_lot::Mutex;
+use thiserror::Error;
+
+#[derive(Debug, Error, MbError)]
+enum Error {
+    #[error("Error({0})")]
+    Error(anyhow::Error),
+}
+
+impl<M: Message> From<error::Error<M>> for Error {
+    fn from(err: error::Error<M>) -> Self {
+        Self::Error(err.into())
+    }
+}
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct MsgU32(pub u32);
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct WorkerId(pub usize);
+
+struct Worker {
+    id: usize,
+    log: Arc<Mutex<Vec<(u32, usize)>>>,
+}
+
+#[async_trait]
+impl AsyncHandler<MsgU32> for Worker {
+    type Error = Error;
+    type Response = WorkerId;
+
+    async fn handle(&self, msg: MsgU32, _bus: &Bus) -> Result<Self::Response, Self::Error> {
+        self.log.lock().push((msg.0, self.id));
+
+        Ok(WorkerId(self.id))
+    }
+}
+
+async fn route_random(seed: u64) -> Vec<(u32, usize)> {
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let mut builder = Bus::build().seed(seed);
+
+    for id in 0..3 {
+        builder = builder
+            .register(Worker {
+                id,
+                log: log.clone(),
+            })
+            .subscribe_async::<MsgU32>(8, receivers::BufferUnorderedConfig::default())
+            .done();
+    }
+
+    let (b, poller) = builder.build();
+
+    for i in 0..32 {
+        b.send_ext(MsgU32(i), SendOptions::Random).await.unwrap();
+    }
+
+    b.flush_all().await;
+    b.close().await;
+    poller.await;
+
+    let mut log = log.lock().clone();
+    log.sort_unstable();
+    log
+}
+
+#[tokio::test]
+async fn test_random_seeded() {
+    let first = route_random(42).await;
+    let second = route_random(42).await;
+
+    assert_eq!(first.len(), 32);
+    assert_eq!(first, second);
+}
+
+#[tokio::test]
+async fn test_round_robin() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let mut builder = Bus::build();
+
+    for id in 0..3 {
+        builder = builder
+            .register(Worker {
+                id,
+                log: log.clone(),
+            })
+            .subscribe_async::<MsgU32>(8, receivers::BufferUnorderedConfig::default())
+            .done();
+    }
+
+    let (b, poller) = builder.build();
+
+    for i in 0..9 {
+        b.send_boxed(Box::new(MsgU32(i)), SendOptions::RoundRobin)
+            .await
+            .unwrap();
+    }
+
+    b.flush_all().await;
+
+    let mut counts = [0; 3];
+    for (_, id) in log.lock().iter() {
+        counts[*id] += 1;
+    }
+
+    assert_eq!(counts, [3, 3, 3]);
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_round_robin_request() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let mut builder = Bus::build();
+
+    for id in 0..3 {
+        builder = builder
+            .register(Worker {
+                id,
+                log: log.clone(),
+            })
+            .subscribe_async::<MsgU32>(8, receivers::BufferUnorderedConfig::default())
+            .done();
+    }
+
+    let (b, poller) = builder.build();
+    let hits = Arc::new(AtomicUsize::new(0));
+
+    let mut ids = Vec::new();
+    for i in 0..6 {
+        let WorkerId(id) = b
+            .request::<_, WorkerId>(MsgU32(i), SendOptions::RoundRobin)
+            .await
+            .unwrap();
+
+        hits.fetch_add(1, Ordering::SeqCst);
+        ids.push(id);
+    }
+
+    assert_eq!(hits.load(Ordering::SeqCst), 6);
+    assert_eq!(&ids[..3], &ids[3..]);
+
+    ids.truncate(3);
+    ids.sort_unstable();
+    assert_eq!(ids, vec![0, 1, 2]);
+
+    b.close().await;
+    poller.await;
+}
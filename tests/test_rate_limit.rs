@@ -0,0 +1,115 @@
+use std::{
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use messagebus::{
+    derive::{Error as MbError, Message},
+    error::{self, SendError},
+    receivers::{BufferUnorderedConfig, RateLimit},
+    AsyncHandler, Bus, Message,
+};
+use thiserror::Error;
+
+#[derive(Debug, Error, MbError)]
+enum Error {
+    #[error("Error({0})")]
+    Error(anyhow::Error),
+}
+
+impl<M: Message> From<error::Error<M>> for Error {
+    fn from(err: error::Error<M>) -> Self {
+        Self::Error(err.into())
+    }
+}
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Msg;
+
+struct TmpReceiver {
+    handled: Arc<AtomicU32>,
+}
+
+#[async_trait]
+impl AsyncHandler<Msg> for TmpReceiver {
+    type Error = Error;
+    type Response = ();
+
+    async fn handle(&self, _msg: Msg, _bus: &Bus) -> Result<Self::Response, Self::Error> {
+        self.handled.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_rate_limit_try_send() {
+    let handled = Arc::new(AtomicU32::new(0));
+
+    let (b, poller) = Bus::build()
+        .register(TmpReceiver {
+            handled: handled.clone(),
+        })
+        .subscribe_async::<Msg>(
+            8,
+            BufferUnorderedConfig {
+                rate_limit: RateLimit::new(10.0).burst(2),
+                ..Default::default()
+            },
+        )
+        .done()
+        .build();
+
+    b.try_send(Msg).unwrap();
+    b.try_send(Msg).unwrap();
+    assert!(matches!(
+        b.try_send(Msg),
+        Err(error::Error::SendError(SendError::Full(_)))
+    ));
+
+    tokio::time::sleep(Duration::from_millis(120)).await;
+    b.try_send(Msg).unwrap();
+
+    b.flush_all().await;
+    assert_eq!(handled.load(Ordering::SeqCst), 3);
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_rate_limit_send_waits() {
+    let handled = Arc::new(AtomicU32::new(0));
+
+    let (b, poller) = Bus::build()
+        .register(TmpReceiver {
+            handled: handled.clone(),
+        })
+        .subscribe_async::<Msg>(
+            8,
+            BufferUnorderedConfig {
+                rate_limit: RateLimit::new(50.0),
+                ..Default::default()
+            },
+        )
+        .done()
+        .build();
+
+    let start = tokio::time::Instant::now();
+    for _ in 0..5 {
+        b.send(Msg).await.unwrap();
+    }
+
+    // the first message uses the initial token, every next one waits ~20ms
+    assert!(start.elapsed() >= Duration::from_millis(70));
+
+    b.flush_all().await;
+    assert_eq!(handled.load(Ordering::SeqCst), 5);
+
+    b.close().await;
+    poller.await;
+}
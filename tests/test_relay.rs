@@ -186,6 +186,7 @@ async fn test_relay() {
             receivers::BufferUnorderedConfig {
                 buffer_size: 1,
                 max_parallel: 1,
+                ..Default::default()
             },
         )
         .done()
@@ -0,0 +1,204 @@
+use std::{
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use messagebus::{
+    derive::{Error as MbError, Message},
+    error::{self, StdSyncSendError},
+    receivers, AsyncHandler, Bus, Message, Middleware, SendOptions, TypeTag,
+};
+use thiserror::Error;
+
+#[derive(Debug, Error, MbError)]
+enum Error {
+    #[error("Error({0})")]
+    Error(anyhow::Error),
+}
+
+impl<M: Message> From<error::Error<M>> for Error {
+    fn from(err: error::Error<M>) -> Self {
+        Self::Error(err.into())
+    }
+}
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Query(pub u32);
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Answer(pub u32);
+
+struct Shard {
+    factor: u32,
+}
+
+#[async_trait]
+impl AsyncHandler<Query> for Shard {
+    type Error = Error;
+    type Response = Answer;
+
+    async fn handle(&self, msg: Query, _bus: &Bus) -> Result<Self::Response, Self::Error> {
+        tokio::time::sleep(Duration::from_millis(self.factor as u64 * 10)).await;
+
+        // a shard with no factor is down
+        if self.factor == 0 {
+            return Err(Error::Error(anyhow::anyhow!("shard down")));
+        }
+
+        Ok(Answer(msg.0 * self.factor))
+    }
+}
+
+struct CountSends(Arc<AtomicU32>);
+
+#[async_trait]
+impl Middleware for CountSends {
+    async fn on_send(
+        &self,
+        _tt: &TypeTag,
+        _msg: &mut dyn Message,
+        _bus: &Bus,
+    ) -> Result<(), Box<dyn StdSyncSendError>> {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_request_all() {
+    let mut builder = Bus::build();
+
+    for factor in 1..=3 {
+        builder = builder
+            .register(Shard { factor })
+            .subscribe_async::<Query>(8, receivers::BufferUnorderedConfig::default())
+            .done();
+    }
+
+    let (b, poller) = builder.build();
+
+    let resps = b
+        .request_all::<_, Answer>(Query(10), SendOptions::Broadcast)
+        .collect::<Vec<_>>()
+        .await;
+
+    let mut answers = resps
+        .iter()
+        .map(|(_, resp)| resp.as_ref().unwrap().0)
+        .collect::<Vec<_>>();
+
+    answers.sort_unstable();
+    assert_eq!(answers, vec![10, 20, 30]);
+
+    let mut ids = resps.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+    ids.sort_unstable();
+    ids.dedup();
+    assert_eq!(ids.len(), 3);
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_request_quorum() {
+    let mut builder = Bus::build();
+
+    for factor in 1..=3 {
+        builder = builder
+            .register(Shard { factor })
+            .subscribe_async::<Query>(8, receivers::BufferUnorderedConfig::default())
+            .done();
+    }
+
+    let (b, poller) = builder.build();
+
+    let resps = b
+        .request_quorum::<_, Answer>(Query(1), SendOptions::Broadcast, 2)
+        .await
+        .unwrap();
+
+    let answers = resps
+        .into_iter()
+        .map(|(_, resp)| resp.0)
+        .collect::<Vec<_>>();
+
+    assert_eq!(answers, vec![1, 2]);
+
+    b.flush_all().await;
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_request_all_fold() {
+    let mut builder = Bus::build();
+
+    for factor in 1..=3 {
+        builder = builder
+            .register(Shard { factor })
+            .subscribe_async::<Query>(8, receivers::BufferUnorderedConfig::default())
+            .done();
+    }
+
+    let (b, poller) = builder.build();
+
+    let sum = b
+        .request_all_fold(
+            Query(2),
+            SendOptions::Broadcast,
+            0,
+            |acc, _, resp: Result<Answer, _>| acc + resp.unwrap().0,
+        )
+        .await;
+
+    assert_eq!(sum, 12);
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_request_quorum_errors() {
+    let sends = Arc::new(AtomicU32::new(0));
+    let mut builder = Bus::build().middleware(CountSends(sends.clone()));
+
+    // the failing shard answers first
+    for factor in [0, 0, 2, 3] {
+        builder = builder
+            .register(Shard { factor })
+            .subscribe_async::<Query>(8, receivers::BufferUnorderedConfig::default())
+            .done();
+    }
+
+    let (b, poller) = builder.build();
+
+    // the errors do not count toward the quorum
+    let resps = b
+        .request_quorum::<_, Answer>(Query(1), SendOptions::Broadcast, 2)
+        .await
+        .unwrap();
+
+    let answers = resps.iter().map(|(_, resp)| resp.0).collect::<Vec<_>>();
+    assert_eq!(answers, vec![2, 3]);
+
+    // the middleware saw the request once, not once per shard
+    assert_eq!(sends.load(Ordering::Relaxed), 1);
+
+    // two shards down out of four: three answers can not be reached
+    let res = b
+        .request_quorum::<_, Answer>(Query(1), SendOptions::Broadcast, 3)
+        .await;
+
+    assert!(matches!(res, Err(error::Error::OtherBoxed(_))));
+
+    b.flush_all().await;
+    b.close().await;
+    poller.await;
+}
@@ -0,0 +1,126 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use messagebus::{
+    derive::{Error as MbError, Message},
+    error, receivers, AsyncHandler, Bus, Message, SendOptions,
+};
+use thiserror::Error;
+
+#[derive(Debug, Error, MbError)]
+enum Error {
+    #[error("Error({0})")]
+    Error(anyhow::Error),
+}
+
+impl<M: Message> From<error::Error<M>> for Error {
+    fn from(err: error::Error<M>) -> Self {
+        Self::Error(err.into())
+    }
+}
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Sleep(pub u64);
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Done;
+
+struct Sleeper {
+    cancelled: Arc<AtomicBool>,
+}
+
+#[async_trait]
+impl AsyncHandler<Sleep> for Sleeper {
+    type Error = Error;
+    type Response = Done;
+
+    async fn handle(&self, msg: Sleep, bus: &Bus) -> Result<Self::Response, Self::Error> {
+        tokio::time::sleep(Duration::from_millis(msg.0)).await;
+        self.cancelled.store(bus.is_cancelled(), Ordering::SeqCst);
+
+        Ok(Done)
+    }
+}
+
+#[tokio::test]
+async fn test_request_timeout() {
+    let cancelled = Arc::new(AtomicBool::new(false));
+
+    let (b, poller) = Bus::build()
+        .register(Sleeper {
+            cancelled: cancelled.clone(),
+        })
+        .subscribe_async::<Sleep>(8, receivers::BufferUnorderedConfig::default())
+        .done()
+        .build();
+
+    let res = b
+        .request_timeout::<_, Done>(
+            Sleep(100),
+            SendOptions::Broadcast,
+            Duration::from_millis(10),
+        )
+        .await;
+
+    assert!(matches!(res, Err(error::Error::Timeout)));
+
+    b.flush_all().await;
+    assert!(cancelled.load(Ordering::SeqCst));
+
+    let res = b
+        .request_timeout::<_, Done>(Sleep(1), SendOptions::Broadcast, Duration::from_secs(1))
+        .await;
+
+    assert!(res.is_ok());
+    assert!(!cancelled.load(Ordering::SeqCst));
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_request_dropped() {
+    let cancelled = Arc::new(AtomicBool::new(false));
+
+    let (b, poller) = Bus::build()
+        .register(Sleeper {
+            cancelled: cancelled.clone(),
+        })
+        .subscribe_async::<Sleep>(8, receivers::BufferUnorderedConfig::default())
+        .done()
+        .build();
+
+    let bus = b.clone();
+    let task = tokio::spawn(async move {
+        bus.request::<_, Done>(Sleep(100), SendOptions::Broadcast)
+            .await
+            .unwrap()
+    });
+
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    task.abort();
+
+    b.flush_all().await;
+    assert!(cancelled.load(Ordering::SeqCst));
+
+    let res = b
+        .request_with_deadline::<_, Done>(
+            Sleep(1),
+            SendOptions::Broadcast,
+            tokio::time::Instant::now() + Duration::from_secs(1),
+        )
+        .await;
+
+    assert!(res.is_ok());
+
+    b.close().await;
+    poller.await;
+}
@@ -0,0 +1,215 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use messagebus::{
+    derive::{Error as MbError, Message},
+    error,
+    receivers::BufferUnorderedConfig,
+    AsyncHandler, Bus, Headers, Message, TypeTagged,
+};
+use parking_lot::Mutex;
+use thiserror::Error;
+
+#[derive(Debug, Error, MbError)]
+enum Error {
+    #[error("Error({0})")]
+    Error(anyhow::Error),
+}
+
+impl<M: Message> From<error::Error<M>> for Error {
+    fn from(err: error::Error<M>) -> Self {
+        Self::Error(err.into())
+    }
+}
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Input(u32);
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Doubled(u32);
+
+struct Doubler;
+
+#[async_trait]
+impl AsyncHandler<Input> for Doubler {
+    type Error = Error;
+    type Response = Doubled;
+
+    async fn handle(&self, msg: Input, _bus: &Bus) -> Result<Self::Response, Self::Error> {
+        Ok(Doubled(msg.0 * 2))
+    }
+}
+
+// takes longer than the ttl of the inputs in `test_resend_context`
+struct SlowDoubler;
+
+#[async_trait]
+impl AsyncHandler<Input> for SlowDoubler {
+    type Error = Error;
+    type Response = Doubled;
+
+    async fn handle(&self, msg: Input, _bus: &Bus) -> Result<Self::Response, Self::Error> {
+        if msg.0 == 0 {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+
+        Ok(Doubled(msg.0 * 2))
+    }
+}
+
+struct Collector {
+    received: Arc<Mutex<Vec<u32>>>,
+}
+
+#[async_trait]
+impl AsyncHandler<Doubled> for Collector {
+    type Error = Error;
+    type Response = ();
+
+    async fn handle(&self, msg: Doubled, _bus: &Bus) -> Result<Self::Response, Self::Error> {
+        self.received.lock().push(msg.0);
+        Ok(())
+    }
+}
+
+struct Tracer {
+    traces: Arc<Mutex<Vec<(u32, Option<String>)>>>,
+}
+
+#[async_trait]
+impl AsyncHandler<Doubled> for Tracer {
+    type Error = Error;
+    type Response = ();
+
+    async fn handle(&self, msg: Doubled, bus: &Bus) -> Result<Self::Response, Self::Error> {
+        let trace = bus
+            .headers()
+            .and_then(|h| h.get("trace"))
+            .map(str::to_string);
+
+        self.traces.lock().push((msg.0, trace));
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_resend_unused_response() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, poller) = Bus::build()
+        .register(Doubler)
+        .subscribe_async::<Input>(
+            8,
+            BufferUnorderedConfig {
+                resend_unused_resp: true,
+                ..Default::default()
+            },
+        )
+        .done()
+        .register(Collector {
+            received: received.clone(),
+        })
+        .subscribe_async::<Doubled>(8, Default::default())
+        .done()
+        .build();
+
+    for i in 1..=3 {
+        b.send(Input(i)).await.unwrap();
+    }
+
+    b.flush_all().await;
+
+    let mut values = received.lock().clone();
+    values.sort_unstable();
+    assert_eq!(values, vec![2, 4, 6]);
+
+    // a response somebody waits for is not published
+    let Doubled(value) = b
+        .request::<_, Doubled>(Input(5), Default::default())
+        .await
+        .unwrap();
+    assert_eq!(value, 10);
+
+    b.flush_all().await;
+    assert_eq!(received.lock().len(), 3);
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_unused_response_dropped_by_default() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, poller) = Bus::build()
+        .register(Doubler)
+        .subscribe_async::<Input>(
+            8,
+            BufferUnorderedConfig {
+                resend_unused_resp: false,
+                ..Default::default()
+            },
+        )
+        .done()
+        .register(Collector {
+            received: received.clone(),
+        })
+        .subscribe_async::<Doubled>(8, Default::default())
+        .done()
+        .build();
+
+    b.send(Input(1)).await.unwrap();
+    b.flush_all().await;
+    assert!(received.lock().is_empty());
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_resend_context() {
+    let traces = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, poller) = Bus::build()
+        .register(SlowDoubler)
+        .subscribe_async::<Input>(
+            8,
+            BufferUnorderedConfig {
+                resend_unused_resp: true,
+                ..Default::default()
+            },
+        )
+        .done()
+        .register(Tracer {
+            traces: traces.clone(),
+        })
+        .subscribe_async::<Doubled>(8, Default::default())
+        .done()
+        .build();
+
+    // the headers of the input carry over to its response
+    b.send_with_headers(Input(1), Headers::new().with("trace", "abc"))
+        .await
+        .unwrap();
+    b.flush_all().await;
+    assert_eq!(traces.lock().clone(), vec![(2, Some("abc".to_string()))]);
+
+    // and so does its deadline, which passes while it is handled
+    b.send_with_ttl(Input(0), Duration::from_millis(20))
+        .await
+        .unwrap();
+    b.flush_all().await;
+    assert_eq!(traces.lock().len(), 1);
+
+    let expired = b
+        .stats()
+        .find(|s| s.msg_type_tag == Doubled::type_tag_())
+        .unwrap()
+        .expired_count;
+    assert_eq!(expired, 1);
+
+    b.close().await;
+    poller.await;
+}
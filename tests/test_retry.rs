@@ -0,0 +1,212 @@
+use std::{
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use messagebus::{
+    derive::{Error as MbError, Message},
+    error,
+    receivers::{BufferUnorderedConfig, RetryPolicy, SynchronizedConfig},
+    AsyncHandler, Bus, Message, SynchronizedHandler,
+};
+use thiserror::Error;
+
+#[derive(Debug, Error, MbError)]
+enum Error {
+    #[error("Transient")]
+    Transient,
+
+    #[error("Fatal")]
+    Fatal,
+
+    #[error("Error({0})")]
+    Error(anyhow::Error),
+}
+
+impl<M: Message> From<error::Error<M>> for Error {
+    fn from(err: error::Error<M>) -> Self {
+        Self::Error(err.into())
+    }
+}
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Req(u32);
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Resp(u32);
+
+struct FlakyReceiver {
+    attempts: Arc<AtomicU32>,
+}
+
+#[async_trait]
+impl AsyncHandler<Req> for FlakyReceiver {
+    type Error = Error;
+    type Response = Resp;
+
+    async fn handle(&self, msg: Req, _bus: &Bus) -> Result<Self::Response, Self::Error> {
+        let attempt = self.attempts.fetch_add(1, Ordering::SeqCst) + 1;
+
+        if attempt < msg.0 {
+            Err(Error::Transient)
+        } else if msg.0 == 0 {
+            Err(Error::Fatal)
+        } else {
+            Ok(Resp(attempt))
+        }
+    }
+}
+
+struct FailingReceiver {
+    attempts: Arc<AtomicU32>,
+}
+
+impl SynchronizedHandler<Req> for FailingReceiver {
+    type Error = Error;
+    type Response = Resp;
+
+    fn handle(&mut self, _msg: Req, _bus: &Bus) -> Result<Self::Response, Self::Error> {
+        self.attempts.fetch_add(1, Ordering::SeqCst);
+        Err(Error::Transient)
+    }
+}
+
+fn is_transient(err: &(dyn std::error::Error + 'static)) -> bool {
+    matches!(err.downcast_ref::<Error>(), Some(Error::Transient))
+}
+
+#[tokio::test]
+async fn test_retry_until_success() {
+    let attempts = Arc::new(AtomicU32::new(0));
+
+    let (b, poller) = Bus::build()
+        .register(FlakyReceiver {
+            attempts: attempts.clone(),
+        })
+        .subscribe_async::<Req>(
+            8,
+            BufferUnorderedConfig {
+                retry: RetryPolicy::new(5)
+                    .backoff(Duration::from_millis(1), Duration::from_millis(5))
+                    .jitter(0.5),
+                ..Default::default()
+            },
+        )
+        .done()
+        .build();
+
+    let resp: Resp = b.request(Req(3), Default::default()).await.unwrap();
+
+    assert_eq!(resp.0, 3);
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+    b.flush_all().await;
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_retry_predicate() {
+    let attempts = Arc::new(AtomicU32::new(0));
+
+    let (b, poller) = Bus::build()
+        .register(FlakyReceiver {
+            attempts: attempts.clone(),
+        })
+        .subscribe_async::<Req>(
+            8,
+            BufferUnorderedConfig {
+                retry: RetryPolicy::new(5)
+                    .backoff(Duration::from_millis(1), Duration::from_millis(5))
+                    .retry_if(is_transient),
+                ..Default::default()
+            },
+        )
+        .done()
+        .build();
+
+    let resp = b.request::<_, Resp>(Req(0), Default::default()).await;
+
+    assert!(resp.is_err());
+    assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+    b.flush_all().await;
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_retry_exhausted() {
+    let attempts = Arc::new(AtomicU32::new(0));
+
+    let (b, poller) = Bus::build()
+        .register_unsync(FailingReceiver {
+            attempts: attempts.clone(),
+        })
+        .subscribe_sync::<Req>(
+            8,
+            SynchronizedConfig {
+                retry: RetryPolicy::new(3)
+                    .backoff(Duration::from_millis(1), Duration::from_millis(2)),
+                ..Default::default()
+            },
+        )
+        .done()
+        .build();
+
+    let resp = b.request::<_, Resp>(Req(1), Default::default()).await;
+
+    assert!(resp.is_err());
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+    b.flush_all().await;
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_retry_stops_when_expired() {
+    let attempts = Arc::new(AtomicU32::new(0));
+
+    let (b, poller) = Bus::build()
+        .register_unsync(FailingReceiver {
+            attempts: attempts.clone(),
+        })
+        .subscribe_sync::<Req>(
+            8,
+            SynchronizedConfig {
+                retry: RetryPolicy::new(10)
+                    .backoff(Duration::from_millis(40), Duration::from_millis(40)),
+                ..Default::default()
+            },
+        )
+        .done()
+        .build();
+
+    b.send_with_ttl(Req(1), Duration::from_millis(50))
+        .await
+        .unwrap();
+    b.flush_all().await;
+
+    // no retry is made once the message has expired
+    assert!(attempts.load(Ordering::SeqCst) < 4);
+
+    b.close().await;
+    poller.await;
+}
+
+#[test]
+fn test_retry_delay() {
+    let policy = RetryPolicy::new(10).backoff(Duration::from_millis(10), Duration::from_millis(50));
+
+    assert_eq!(policy.delay(1), Duration::from_millis(10));
+    assert_eq!(policy.delay(2), Duration::from_millis(20));
+    assert_eq!(policy.delay(3), Duration::from_millis(40));
+    assert_eq!(policy.delay(4), Duration::from_millis(50));
+}
@@ -0,0 +1,111 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use async_trait::async_trait;
+use messagebus::{
+    derive::{Error as MbError, Message},
+    error, receivers, AsyncHandler, Bus, Message, Module,
+};
+use thiserror::Error;
+
+#[derive(Debug, Error, MbError)]
+enum Error {
+    #[error("Error({0})")]
+    Error(anyhow::Error),
+}
+
+impl<M: Message> From<error::Error<M>> for Error {
+    fn from(err: error::Error<M>) -> Self {
+        Self::Error(err.into())
+    }
+}
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct MsgU32(pub u32);
+
+struct Worker {
+    counter: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl AsyncHandler<MsgU32> for Worker {
+    type Error = Error;
+    type Response = ();
+
+    async fn handle(&self, _msg: MsgU32, _bus: &Bus) -> Result<Self::Response, Self::Error> {
+        self.counter.fetch_add(1, Ordering::SeqCst);
+
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_register_at_runtime() {
+    let counter1 = Arc::new(AtomicUsize::new(0));
+    let counter2 = Arc::new(AtomicUsize::new(0));
+
+    let (b, poller) = Bus::build()
+        .register(Worker {
+            counter: counter1.clone(),
+        })
+        .subscribe_async::<MsgU32>(8, receivers::BufferUnorderedConfig::default())
+        .done()
+        .build();
+
+    b.send(MsgU32(1)).await.unwrap();
+    b.flush_all().await;
+
+    let handle = b
+        .register(Worker {
+            counter: counter2.clone(),
+        })
+        .subscribe_async::<MsgU32>(8, receivers::BufferUnorderedConfig::default())
+        .done();
+
+    b.send(MsgU32(2)).await.unwrap();
+    b.flush_all().await;
+
+    assert_eq!(counter1.load(Ordering::SeqCst), 2);
+    assert_eq!(counter2.load(Ordering::SeqCst), 1);
+    assert_eq!(b.stats().count(), 2);
+
+    handle.remove().await;
+
+    b.send(MsgU32(3)).await.unwrap();
+    b.flush_all().await;
+
+    assert_eq!(counter1.load(Ordering::SeqCst), 3);
+    assert_eq!(counter2.load(Ordering::SeqCst), 1);
+    assert_eq!(b.stats().count(), 1);
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_add_module_at_runtime() {
+    let counter = Arc::new(AtomicUsize::new(0));
+
+    let (b, poller) = Bus::build().build();
+
+    let module = Module::new()
+        .register(Worker {
+            counter: counter.clone(),
+        })
+        .subscribe_async::<MsgU32>(8, receivers::BufferUnorderedConfig::default())
+        .done();
+
+    let handle = b.add_module(module);
+    assert_eq!(handle.receiver_ids().count(), 1);
+
+    b.send(MsgU32(1)).await.unwrap();
+    b.flush_all().await;
+    assert_eq!(counter.load(Ordering::SeqCst), 1);
+
+    handle.remove().await;
+    b.close().await;
+    poller.await;
+}
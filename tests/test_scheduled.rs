@@ -0,0 +1,117 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use messagebus::{
+    derive::{Error as MbError, Message},
+    error, AsyncHandler, Bus, Message,
+};
+use parking_lot::Mutex;
+use thiserror::Error;
+
+#[derive(Debug, Error, MbError)]
+enum Error {
+    #[error("Error({0})")]
+    Error(anyhow::Error),
+}
+
+impl<M: Message> From<error::Error<M>> for Error {
+    fn from(err: error::Error<M>) -> Self {
+        Self::Error(err.into())
+    }
+}
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Msg(u32);
+
+struct TmpReceiver {
+    received: Arc<Mutex<Vec<u32>>>,
+}
+
+#[async_trait]
+impl AsyncHandler<Msg> for TmpReceiver {
+    type Error = Error;
+    type Response = ();
+
+    async fn handle(&self, msg: Msg, _bus: &Bus) -> Result<Self::Response, Self::Error> {
+        self.received.lock().push(msg.0);
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_send_after() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, poller) = Bus::build()
+        .register(TmpReceiver {
+            received: received.clone(),
+        })
+        .subscribe_async::<Msg>(8, Default::default())
+        .done()
+        .build();
+
+    let start = Instant::now();
+    b.send_after(Duration::from_millis(50), Msg(1)).unwrap();
+
+    b.flush_all().await;
+    assert!(received.lock().is_empty());
+
+    b.flush_all_ext(true).await;
+    assert_eq!(received.lock().clone(), vec![1]);
+    assert!(start.elapsed() >= Duration::from_millis(50));
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_send_at_order() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, poller) = Bus::build()
+        .register(TmpReceiver {
+            received: received.clone(),
+        })
+        .subscribe_async::<Msg>(8, Default::default())
+        .done()
+        .build();
+
+    let now = tokio::time::Instant::now();
+    b.send_at(now + Duration::from_millis(60), Msg(3)).unwrap();
+    b.send_at(now + Duration::from_millis(20), Msg(1)).unwrap();
+    b.send_at(now + Duration::from_millis(40), Msg(2)).unwrap();
+
+    b.idle_all_ext(true).await;
+    assert_eq!(received.lock().clone(), vec![1, 2, 3]);
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_close_cancels_scheduled() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, poller) = Bus::build()
+        .register(TmpReceiver {
+            received: received.clone(),
+        })
+        .subscribe_async::<Msg>(8, Default::default())
+        .done()
+        .build();
+
+    b.send_after(Duration::from_secs(30), Msg(1)).unwrap();
+
+    b.close().await;
+    tokio::time::timeout(Duration::from_secs(1), poller)
+        .await
+        .unwrap();
+
+    b.flush_all_ext(true).await;
+    assert!(received.lock().is_empty());
+    assert!(b.send_after(Duration::from_millis(1), Msg(2)).is_err());
+}
@@ -0,0 +1,233 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use messagebus::{
+    derive::{Error as MbError, Message},
+    error,
+    receivers::BufferUnorderedConfig,
+    AsyncHandler, BatchHandler, Bus, Message,
+};
+use parking_lot::Mutex;
+use thiserror::Error;
+
+#[derive(Debug, Error, Clone, MbError)]
+enum Error {
+    #[error("Error({0})")]
+    Error(Arc<anyhow::Error>),
+}
+
+impl<M: Message> From<error::Error<M>> for Error {
+    fn from(err: error::Error<M>) -> Self {
+        Self::Error(Arc::new(err.into()))
+    }
+}
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Work(u32, u64);
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Parent(u32);
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Batched(u32);
+
+struct Worker {
+    done: Arc<Mutex<Vec<u32>>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+#[async_trait]
+impl AsyncHandler<Work> for Worker {
+    type Error = Error;
+    type Response = ();
+
+    async fn handle(&self, msg: Work, bus: &Bus) -> Result<Self::Response, Self::Error> {
+        tokio::time::sleep(Duration::from_millis(msg.1)).await;
+
+        if bus.is_cancelled() {
+            self.cancelled.store(true, Ordering::SeqCst);
+        }
+
+        self.done.lock().push(msg.0);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AsyncHandler<Parent> for Worker {
+    type Error = Error;
+    type Response = ();
+
+    async fn handle(&self, msg: Parent, bus: &Bus) -> Result<Self::Response, Self::Error> {
+        bus.send(Work(msg.0 + 100, 30)).await?;
+        Ok(())
+    }
+}
+
+impl BatchHandler<Batched> for Worker {
+    type Error = Error;
+    type Response = ();
+    type InBatch = Vec<Batched>;
+    type OutBatch = Vec<()>;
+
+    fn handle(&self, msgs: Vec<Batched>, _bus: &Bus) -> Result<Vec<()>, Self::Error> {
+        self.done.lock().extend(msgs.iter().map(|m| m.0));
+        Ok(vec![(); msgs.len()])
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_scope_idle() {
+    let done = Arc::new(Mutex::new(Vec::new()));
+    let cancelled = Arc::new(AtomicBool::new(false));
+
+    let (b, poller) = Bus::build()
+        .register(Worker {
+            done: done.clone(),
+            cancelled: cancelled.clone(),
+        })
+        .subscribe_async::<Work>(8, Default::default())
+        .subscribe_async::<Parent>(8, Default::default())
+        .subscribe_batch_sync::<Batched>(8, Default::default())
+        .done()
+        .build();
+
+    b.send(Work(1, 300)).await.unwrap();
+
+    let start = tokio::time::Instant::now();
+    let scope = b.enter();
+    scope.send(Work(2, 10)).await.unwrap();
+    scope.send(Parent(3)).await.unwrap();
+    assert!(scope.pending() > 0);
+
+    scope.idle().await;
+    assert!(start.elapsed() < Duration::from_millis(250));
+    assert_eq!(scope.pending(), 0);
+
+    let mut values = done.lock().clone();
+    values.sort_unstable();
+    assert_eq!(values, vec![2, 103]);
+
+    b.flush_all().await;
+    assert_eq!(done.lock().len(), 3);
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_scope_flush() {
+    let done = Arc::new(Mutex::new(Vec::new()));
+    let cancelled = Arc::new(AtomicBool::new(false));
+
+    let (b, poller) = Bus::build()
+        .register(Worker {
+            done: done.clone(),
+            cancelled: cancelled.clone(),
+        })
+        .subscribe_async::<Work>(8, Default::default())
+        .subscribe_async::<Parent>(8, Default::default())
+        .subscribe_batch_sync::<Batched>(8, Default::default())
+        .done()
+        .build();
+
+    let scope = b.enter();
+    for i in 0..3 {
+        scope.send(Batched(i)).await.unwrap();
+    }
+
+    // less than a batch: nothing is handled until flushed
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    assert_eq!(scope.pending(), 3);
+
+    scope.flush().await;
+    assert_eq!(scope.pending(), 0);
+    assert_eq!(done.lock().clone(), vec![0, 1, 2]);
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_scope_nested() {
+    let done = Arc::new(Mutex::new(Vec::new()));
+    let cancelled = Arc::new(AtomicBool::new(false));
+
+    let (b, poller) = Bus::build()
+        .register(Worker {
+            done: done.clone(),
+            cancelled: cancelled.clone(),
+        })
+        .subscribe_async::<Work>(8, Default::default())
+        .subscribe_async::<Parent>(8, Default::default())
+        .subscribe_batch_sync::<Batched>(8, Default::default())
+        .done()
+        .build();
+
+    let outer = b.enter();
+    let inner = outer.enter();
+
+    inner.send(Work(1, 20)).await.unwrap();
+    assert_eq!(outer.pending(), 1);
+
+    outer.idle().await;
+    assert_eq!(inner.pending(), 0);
+    assert_eq!(done.lock().clone(), vec![1]);
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_scope_leave() {
+    let done = Arc::new(Mutex::new(Vec::new()));
+    let cancelled = Arc::new(AtomicBool::new(false));
+
+    let (b, poller) = Bus::build()
+        .register(Worker {
+            done: done.clone(),
+            cancelled: cancelled.clone(),
+        })
+        .subscribe_async::<Work>(
+            8,
+            BufferUnorderedConfig {
+                max_parallel: 1,
+                ..Default::default()
+            },
+        )
+        .done()
+        .build();
+
+    let scope = b.enter();
+    let bus = scope.bus().clone();
+
+    for i in 0..4 {
+        scope.send(Work(i, 50)).await.unwrap();
+    }
+
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    scope.leave();
+    assert!(bus.is_cancelled());
+
+    b.flush_all().await;
+
+    // only the message being handled on leave ran, and it saw the cancellation
+    assert_eq!(done.lock().clone(), vec![0]);
+    assert!(cancelled.load(Ordering::SeqCst));
+
+    b.send(Work(9, 1)).await.unwrap();
+    b.flush_all().await;
+    assert_eq!(done.lock().clone(), vec![0, 9]);
+
+    b.close().await;
+    poller.await;
+}
@@ -0,0 +1,146 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use messagebus::{
+    derive::{Error as MbError, Message},
+    error,
+    receivers::Partitioned,
+    Bus, Message, SynchronizedHandler,
+};
+use parking_lot::Mutex;
+use thiserror::Error;
+
+#[derive(Debug, Error, MbError)]
+enum Error {
+    #[error("Error({0})")]
+    Error(anyhow::Error),
+}
+
+impl<M: Message> From<error::Error<M>> for Error {
+    fn from(err: error::Error<M>) -> Self {
+        Self::Error(err.into())
+    }
+}
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Add {
+    key: u32,
+    value: u32,
+}
+
+impl Partitioned for Add {
+    type Key = u32;
+
+    fn partition_key(&self) -> u32 {
+        self.key
+    }
+}
+
+#[derive(Default)]
+struct Report {
+    // shard handling each key
+    owners: HashMap<u32, u32>,
+    totals: HashMap<u32, u32>,
+    syncs: u32,
+    // shards handling a message right now, and the most there ever were
+    running: u32,
+    max_running: u32,
+}
+
+struct Aggregator {
+    shard: u32,
+    // state owned by the shard
+    totals: HashMap<u32, u32>,
+    report: Arc<Mutex<Report>>,
+}
+
+impl SynchronizedHandler<Add> for Aggregator {
+    type Error = Error;
+    type Response = ();
+
+    fn handle(&mut self, msg: Add, _bus: &Bus) -> Result<Self::Response, Self::Error> {
+        {
+            let mut report = self.report.lock();
+            report.running += 1;
+            report.max_running = report.max_running.max(report.running);
+        }
+
+        std::thread::sleep(Duration::from_millis(20));
+        self.report.lock().running -= 1;
+
+        let total = self.totals.entry(msg.key).or_default();
+        *total += msg.value;
+
+        let mut report = self.report.lock();
+        let owner = *report.owners.entry(msg.key).or_insert(self.shard);
+        assert_eq!(owner, self.shard);
+        report.totals.insert(msg.key, *total);
+
+        Ok(())
+    }
+
+    fn sync(&mut self, _bus: &Bus) -> Result<(), Self::Error> {
+        self.report.lock().syncs += 1;
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_sharded() {
+    let report = Arc::new(Mutex::new(Report::default()));
+    let shard = AtomicU32::new(0);
+
+    let (b, poller) = Bus::build()
+        .register_sharded(
+            || Aggregator {
+                shard: shard.fetch_add(1, Ordering::Relaxed),
+                totals: HashMap::new(),
+                report: report.clone(),
+            },
+            4,
+        )
+        .subscribe_sync::<Add>(64, Default::default())
+        .done()
+        .build();
+
+    assert_eq!(shard.load(Ordering::Relaxed), 4);
+
+    for value in 1..=2 {
+        for key in 0..16 {
+            b.send(Add { key, value }).await.unwrap();
+        }
+    }
+
+    b.flush_all().await;
+
+    {
+        let report = report.lock();
+
+        // the shards handled their messages in parallel
+        assert!(report.max_running > 1);
+        assert!(report.max_running <= 4);
+
+        assert_eq!(report.totals.len(), 16);
+        assert!(report.totals.values().all(|total| *total == 3));
+
+        // the keys are spread over more than one shard
+        let mut shards: Vec<_> = report.owners.values().collect();
+        shards.sort_unstable();
+        shards.dedup();
+        assert!(shards.len() > 1);
+    }
+
+    // sync goes to every shard
+    b.sync_all().await;
+    assert_eq!(report.lock().syncs, 4);
+
+    b.close().await;
+    poller.await;
+}
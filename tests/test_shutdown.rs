@@ -0,0 +1,252 @@
+use std::{pin::Pin, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use futures::{Stream, StreamExt};
+use messagebus::{
+    derive::{Error as MbError, Message},
+    error,
+    receivers::{AsyncProducerConfig, BufferUnorderedConfig},
+    AsyncHandler, AsyncProducer, Bus, Message, ProducerStats, ShutdownMode, ShutdownOptions,
+    ShutdownOutcome,
+};
+use parking_lot::Mutex;
+use thiserror::Error;
+
+#[derive(Debug, Error, MbError)]
+enum Error {
+    #[error("Error({0})")]
+    Error(anyhow::Error),
+}
+
+impl<M: Message> From<error::Error<M>> for Error {
+    fn from(err: error::Error<M>) -> Self {
+        Self::Error(err.into())
+    }
+}
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Work(u32, u64);
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Range(u32);
+
+struct Worker {
+    done: Arc<Mutex<Vec<u32>>>,
+}
+
+#[async_trait]
+impl AsyncHandler<Work> for Worker {
+    type Error = Error;
+    type Response = ();
+
+    async fn handle(&self, msg: Work, _bus: &Bus) -> Result<Self::Response, Self::Error> {
+        tokio::time::sleep(Duration::from_millis(msg.1)).await;
+        self.done.lock().push(msg.0);
+        Ok(())
+    }
+}
+
+struct Producer;
+
+#[async_trait]
+impl AsyncProducer<Range> for Producer {
+    type Item = Work;
+    type Response = ();
+    type Error = Error;
+
+    async fn producer(
+        &self,
+        msg: Range,
+        _bus: &Bus,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<Self::Item, Self::Error>> + Send + '_>>, Self::Error>
+    {
+        Ok(Box::pin(futures::stream::iter(0..msg.0).then(
+            |i| async move {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+                Ok(Work(i, 0))
+            },
+        )))
+    }
+
+    async fn finish(&self, _stats: ProducerStats, _bus: &Bus) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_shutdown_drain() {
+    let done = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, poller) = Bus::build()
+        .register(Worker { done: done.clone() })
+        .subscribe_async::<Work>(
+            8,
+            BufferUnorderedConfig {
+                max_parallel: 1,
+                ..Default::default()
+            },
+        )
+        .done()
+        .register(Producer)
+        .subscribe_producer::<Range>(8, AsyncProducerConfig::default())
+        .done()
+        .build();
+
+    for i in 0..4 {
+        b.send(Work(i, 10)).await.unwrap();
+    }
+
+    let report = b.shutdown(ShutdownOptions::default()).await;
+    assert!(report.is_clean());
+    assert_eq!(done.lock().clone(), vec![0, 1, 2, 3]);
+
+    // producers are closed first
+    assert_eq!(report.receivers.len(), 2);
+    assert!(report.receivers[0].name.contains("AsyncProducer"));
+
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_shutdown_drop() {
+    let done = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, poller) = Bus::build()
+        .register(Worker { done: done.clone() })
+        .subscribe_async::<Work>(
+            8,
+            BufferUnorderedConfig {
+                max_parallel: 1,
+                ..Default::default()
+            },
+        )
+        .done()
+        .register(Producer)
+        .subscribe_producer::<Range>(8, AsyncProducerConfig::default())
+        .done()
+        .build();
+
+    for i in 0..4 {
+        b.send(Work(i, 30)).await.unwrap();
+    }
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    let report = b
+        .shutdown(ShutdownOptions {
+            mode: ShutdownMode::Drop,
+            ..Default::default()
+        })
+        .await;
+
+    assert_eq!(done.lock().clone(), vec![0]);
+    assert!(!report.is_clean());
+    assert_eq!(report.abandoned(), 3);
+    assert!(report
+        .receivers
+        .iter()
+        .all(|r| r.outcome == ShutdownOutcome::Closed));
+
+    poller.await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_shutdown_deadline() {
+    let done = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, _poller) = Bus::build()
+        .register(Worker { done: done.clone() })
+        .subscribe_async::<Work>(
+            8,
+            BufferUnorderedConfig {
+                max_parallel: 1,
+                ..Default::default()
+            },
+        )
+        .done()
+        .register(Producer)
+        .subscribe_producer::<Range>(8, AsyncProducerConfig::default())
+        .done()
+        .build();
+
+    b.send(Work(0, 1000)).await.unwrap();
+    b.send(Work(1, 10)).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    let start = tokio::time::Instant::now();
+    let report = b
+        .shutdown(ShutdownOptions {
+            deadline: Duration::from_millis(50),
+            ..Default::default()
+        })
+        .await;
+
+    assert!(start.elapsed() < Duration::from_millis(500));
+    assert!(done.lock().is_empty());
+
+    let worker = report
+        .receivers
+        .iter()
+        .find(|r| r.outcome == ShutdownOutcome::TimedOut)
+        .unwrap();
+    assert_eq!(worker.abandoned, 2);
+    assert_eq!(report.abandoned(), 2);
+}
+
+#[tokio::test]
+async fn test_shutdown_producer_output() {
+    let done = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, poller) = Bus::build()
+        .register(Worker { done: done.clone() })
+        .subscribe_async::<Work>(8, Default::default())
+        .done()
+        .register(Producer)
+        .subscribe_producer::<Range>(8, AsyncProducerConfig::default())
+        .done()
+        .build();
+
+    b.send(Range(50)).await.unwrap();
+
+    // the consumers take everything the producer publishes while it closes
+    let report = b.shutdown(ShutdownOptions::default()).await;
+    assert!(report.is_clean());
+    assert_eq!(report.refused, 0);
+
+    let mut done = done.lock().clone();
+    done.sort_unstable();
+    assert_eq!(done, (0..50).collect::<Vec<_>>());
+
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_shutdown_unordered_refused() {
+    let done = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, poller) = Bus::build()
+        .register(Worker { done: done.clone() })
+        .subscribe_async::<Work>(8, Default::default())
+        .done()
+        .register(Producer)
+        .subscribe_producer::<Range>(8, AsyncProducerConfig::default())
+        .done()
+        .build();
+
+    b.send(Range(50)).await.unwrap();
+
+    // closed along with the consumers, the producer publishes to a closed bus
+    let report = b
+        .shutdown(ShutdownOptions {
+            ordered: false,
+            ..Default::default()
+        })
+        .await;
+
+    assert!(!report.is_clean());
+    assert!(report.refused > 0);
+    assert_eq!(done.lock().len() as u64 + report.refused, 50);
+
+    poller.await;
+}
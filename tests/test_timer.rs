@@ -0,0 +1,101 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use messagebus::{
+    derive::{Error as MbError, Message},
+    error, AsyncHandler, Bus, Message, TypeTagged,
+};
+use parking_lot::Mutex;
+use thiserror::Error;
+
+#[derive(Debug, Error, MbError)]
+enum Error {
+    #[error("Error({0})")]
+    Error(anyhow::Error),
+}
+
+impl<M: Message> From<error::Error<M>> for Error {
+    fn from(err: error::Error<M>) -> Self {
+        Self::Error(err.into())
+    }
+}
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Tick(u32);
+
+struct TmpReceiver {
+    received: Arc<Mutex<Vec<u32>>>,
+}
+
+#[async_trait]
+impl AsyncHandler<Tick> for TmpReceiver {
+    type Error = Error;
+    type Response = ();
+
+    async fn handle(&self, msg: Tick, _bus: &Bus) -> Result<Self::Response, Self::Error> {
+        self.received.lock().push(msg.0);
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_interval() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let mut counter = 0;
+
+    let (b, poller) = Bus::build()
+        .register_interval(Duration::from_millis(20), move || {
+            counter += 1;
+            Tick(counter)
+        })
+        .register(TmpReceiver {
+            received: received.clone(),
+        })
+        .subscribe_async::<Tick>(8, Default::default())
+        .done()
+        .build();
+
+    tokio::time::sleep(Duration::from_millis(110)).await;
+    b.flush_all().await;
+
+    let stats = b.stats().find(|s| s.has_timer).unwrap();
+    assert_eq!(stats.msg_type_tag, Tick::type_tag_());
+    assert!(stats.timer_running);
+    assert!(stats.timer_ticks >= 3);
+
+    b.close().await;
+    tokio::time::timeout(Duration::from_secs(1), poller)
+        .await
+        .unwrap();
+
+    let stats = b.stats().find(|s| s.has_timer).unwrap();
+    assert!(!stats.timer_running);
+
+    let received = received.lock().clone();
+    assert_eq!(received.len() as i64, stats.timer_ticks);
+    assert_eq!(received, (1..=received.len() as u32).collect::<Vec<_>>());
+}
+
+#[tokio::test]
+async fn test_cron() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, poller) = Bus::build()
+        .register_cron("* * * * * *".parse().unwrap(), || Tick(0))
+        .register(TmpReceiver {
+            received: received.clone(),
+        })
+        .subscribe_async::<Tick>(8, Default::default())
+        .done()
+        .build();
+
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    b.flush_all().await;
+    assert!(!received.lock().is_empty());
+
+    b.close().await;
+    tokio::time::timeout(Duration::from_secs(1), poller)
+        .await
+        .unwrap();
+}
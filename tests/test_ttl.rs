@@ -0,0 +1,165 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use messagebus::{
+    derive::{Error as MbError, Message},
+    error,
+    receivers::BufferUnorderedConfig,
+    AsyncHandler, Bus, Message, TypeTagged,
+};
+use parking_lot::Mutex;
+use thiserror::Error;
+
+#[derive(Debug, Error, MbError)]
+enum Error {
+    #[error("Error({0})")]
+    Error(anyhow::Error),
+}
+
+impl<M: Message> From<error::Error<M>> for Error {
+    fn from(err: error::Error<M>) -> Self {
+        Self::Error(err.into())
+    }
+}
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Msg(u32);
+
+#[derive(Debug, Clone, Message)]
+#[message(clone)]
+struct Resp(u32);
+
+struct TmpReceiver {
+    received: Arc<Mutex<Vec<u32>>>,
+}
+
+#[async_trait]
+impl AsyncHandler<Msg> for TmpReceiver {
+    type Error = Error;
+    type Response = Resp;
+
+    async fn handle(&self, msg: Msg, _bus: &Bus) -> Result<Self::Response, Self::Error> {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        self.received.lock().push(msg.0);
+        Ok(Resp(msg.0))
+    }
+}
+
+fn expired_count(b: &Bus) -> i64 {
+    b.stats()
+        .find(|s| s.msg_type_tag == Msg::type_tag_())
+        .unwrap()
+        .expired_count
+}
+
+#[tokio::test]
+async fn test_ttl_per_send() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, poller) = Bus::build()
+        .register(TmpReceiver {
+            received: received.clone(),
+        })
+        .subscribe_async::<Msg>(
+            8,
+            BufferUnorderedConfig {
+                max_parallel: 1,
+                ..Default::default()
+            },
+        )
+        .done()
+        .build();
+
+    b.send(Msg(0)).await.unwrap();
+    b.send_with_ttl(Msg(1), Duration::from_millis(20))
+        .await
+        .unwrap();
+    b.send_with_ttl(Msg(2), Duration::from_secs(10))
+        .await
+        .unwrap();
+    b.send(Msg(3)).await.unwrap();
+
+    b.flush_all().await;
+    assert_eq!(received.lock().clone(), vec![0, 2, 3]);
+    assert_eq!(expired_count(&b), 1);
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_ttl_expired_request() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, poller) = Bus::build()
+        .register(TmpReceiver {
+            received: received.clone(),
+        })
+        .subscribe_async::<Msg>(
+            8,
+            BufferUnorderedConfig {
+                max_parallel: 1,
+                ..Default::default()
+            },
+        )
+        .done()
+        .build();
+
+    b.send(Msg(0)).await.unwrap();
+
+    let res = b
+        .with_ttl(Duration::from_millis(20))
+        .request::<_, Resp>(Msg(1), Default::default())
+        .await;
+    assert!(matches!(res, Err(error::Error::Expired)));
+
+    // handled in time
+    let Resp(value) = b
+        .with_ttl(Duration::from_secs(10))
+        .request::<_, Resp>(Msg(2), Default::default())
+        .await
+        .unwrap();
+    assert_eq!(value, 2);
+
+    assert_eq!(received.lock().clone(), vec![0, 2]);
+    assert_eq!(expired_count(&b), 1);
+
+    b.close().await;
+    poller.await;
+}
+
+#[tokio::test]
+async fn test_ttl_type_default() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+
+    let (b, poller) = Bus::build()
+        .message_ttl::<Msg>(Duration::from_millis(20))
+        .register(TmpReceiver {
+            received: received.clone(),
+        })
+        .subscribe_async::<Msg>(
+            8,
+            BufferUnorderedConfig {
+                max_parallel: 1,
+                ..Default::default()
+            },
+        )
+        .done()
+        .build();
+
+    b.send(Msg(0)).await.unwrap();
+    b.send(Msg(1)).await.unwrap();
+
+    // a ttl given on send overrides the default of the type
+    b.send_with_ttl(Msg(2), Duration::from_secs(10))
+        .await
+        .unwrap();
+
+    b.flush_all().await;
+    assert_eq!(received.lock().clone(), vec![0, 2]);
+    assert_eq!(expired_count(&b), 1);
+
+    b.close().await;
+    poller.await;
+}